@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use bench_vtables::{
+    collection::{
+        make_collection_inline_sieve, make_collection_multiptr, make_collection_packed_sieve,
+        make_collection_vtable, make_objects, shuffled_kinds, sum_methods, KIND_N,
+    },
+    inline_sieve_table::InlineSievePtr,
+    multi_ptrs::MultiVPtr,
+    packed_sieve_table::PackedSievePtr,
+    v_table::VPtr,
+};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+fn bench_collection(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Collection");
+    for count in [256usize, 4096, 65536].iter() {
+        let kinds = shuffled_kinds(*count, 42);
+        group.bench_with_input(
+            BenchmarkId::new("PackedSievePtr", count),
+            &kinds,
+            |b, kinds| {
+                let tables: Vec<_> = (0..KIND_N).map(make_collection_packed_sieve).collect();
+                let metas: Vec<_> = tables.iter().map(PackedSievePtr::new).collect();
+                // The pointers are those of the tables of each kind.
+                let objects = unsafe { make_objects(kinds, &metas) };
+                b.iter(|| unsafe { sum_methods(black_box(&objects), black_box(3)) })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("InlineSievePtr", count),
+            &kinds,
+            |b, kinds| {
+                let tables: Vec<_> = (0..KIND_N).map(make_collection_inline_sieve).collect();
+                let metas: Vec<_> = tables.iter().map(InlineSievePtr::new).collect();
+                // The pointers are those of the tables of each kind.
+                let objects = unsafe { make_objects(kinds, &metas) };
+                b.iter(|| unsafe { sum_methods(black_box(&objects), black_box(3)) })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("VPtr", count), &kinds, |b, kinds| {
+            let tables: Vec<_> = (0..KIND_N).map(make_collection_vtable).collect();
+            let metas: Vec<_> = tables.iter().map(VPtr::new).collect();
+            // The pointers are those of the tables of each kind.
+            let objects = unsafe { make_objects(kinds, &metas) };
+            b.iter(|| unsafe { sum_methods(black_box(&objects), black_box(3)) })
+        });
+        group.bench_with_input(BenchmarkId::new("MultiVPtr", count), &kinds, |b, kinds| {
+            let tables: Vec<_> = (0..KIND_N).map(make_collection_multiptr).collect();
+            let metas: Vec<_> = tables.iter().map(MultiVPtr::new).collect();
+            // The pointers are those of the tables of each kind.
+            let objects = unsafe { make_objects(kinds, &metas) };
+            b.iter(|| unsafe { sum_methods(black_box(&objects), black_box(3)) })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default().measurement_time(Duration::from_secs(10));
+    targets = bench_collection
+);
+criterion_main!(benches);
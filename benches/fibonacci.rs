@@ -1,71 +1,266 @@
-use std::time::Duration;
+//! Benches `fibonacci` over a matrix of representations, trait counts, methods per trait and `n`.
+//!
+//! By default, only benches the configurations of the README table, with the IDs of its 2021 run,
+//! e.g. `Fibonacci/PackedSievePtr_3trait/10`. Set `FIBONACCI_SWEEP=1` to also bench the whole
+//! matrix, which takes over 7 hours. Its benchmark IDs are
+//! `Fibonacci/<Representation>_T<TRAIT_N>_F<FUNC_N>/<n>`, e.g. `Fibonacci/PackedSievePtr_T3_F3/10`.
+//! Use criterion's filter to run a subset, e.g.
+//! `FIBONACCI_SWEEP=1 cargo bench --bench fibonacci -- 'PackedSievePtr_T3'`.
+use std::{env, time::Duration};
 
 use bench_vtables::{
+    cpp_table::{CppObject, CppPtr},
+    displacement::DisplacementPtr,
     fibonacci::{
-        fibonacci, make_fibonacci_inline_sieve, make_fibonacci_multiptr,
-        make_fibonacci_packed_sieve, make_fibonacci_vtable,
+        fibonacci, fibonacci_trait_ids, make_fibonacci_cpp, make_fibonacci_displacement,
+        make_fibonacci_inline_sieve, make_fibonacci_inline_sieve_sized, make_fibonacci_itab_cache,
+        make_fibonacci_multiptr, make_fibonacci_packed_sieve, make_fibonacci_rel_packed_sieve,
+        make_fibonacci_rel_vtable_sized, make_fibonacci_trait_search, make_fibonacci_vtable,
+        make_fibonacci_vtable_sized,
     },
     inline_sieve_table::InlineSievePtr,
+    itab::ItabPtr,
     multi_ptrs::MultiVPtr,
+    multi_trait_matrix,
     packed_sieve_table::PackedSievePtr,
+    rel_packed_sieve_table::RelPackedSievePtr,
+    rel_v_table::RelVPtr,
+    trait_search::TraitSearchPtr,
     v_table::VPtr,
 };
-use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use criterion::{
+    black_box, criterion_group, criterion_main, measurement::WallTime, BenchmarkGroup, BenchmarkId,
+    Criterion,
+};
 
-fn bench_fibs(c: &mut Criterion) {
-    let mut group = c.benchmark_group("Fibonacci");
-    for i in [10u64].iter() {
-        group.bench_with_input(BenchmarkId::new("PackedSievePtr_2trait", i), i, |b, i| {
-            let table = black_box(make_fibonacci_packed_sieve::<_, 2, 5>());
-            let ptr = PackedSievePtr::new(&table);
-            b.iter(|| fibonacci(black_box(ptr), black_box(*i)))
+/// The values of `n` to compute `fibonacci(n)` of.
+const NS: [u64; 3] = [5, 10, 15];
+
+fn id(representation: &str, trait_n: usize, func_n: usize, n: u64) -> BenchmarkId {
+    BenchmarkId::new(format!("{}_T{}_F{}", representation, trait_n, func_n), n)
+}
+
+fn bench_vptr<const FUNC_N: usize>(group: &mut BenchmarkGroup<WallTime>) {
+    for n in NS.iter() {
+        group.bench_with_input(id("VPtr", 1, FUNC_N, *n), n, |b, n| {
+            let table = black_box(make_fibonacci_vtable_sized::<_, FUNC_N>());
+            let ptr = VPtr::new(&table);
+            b.iter(|| fibonacci(black_box(ptr), black_box(*n)))
         });
-        group.bench_with_input(BenchmarkId::new("PackedSievePtr_3trait", i), i, |b, i| {
-            let table = black_box(make_fibonacci_packed_sieve::<_, 3, 4>());
-            let ptr = PackedSievePtr::new(&table);
-            b.iter(|| fibonacci(black_box(ptr), black_box(*i)))
+    }
+}
+
+fn bench_rel_vptr<const FUNC_N: usize>(group: &mut BenchmarkGroup<WallTime>) {
+    for n in NS.iter() {
+        group.bench_with_input(id("RelVPtr", 1, FUNC_N, *n), n, |b, n| {
+            let table = black_box(make_fibonacci_rel_vtable_sized::<_, FUNC_N>());
+            let ptr = RelVPtr::new(&table);
+            b.iter(|| fibonacci(black_box(ptr), black_box(*n)))
         });
-        group.bench_with_input(BenchmarkId::new("PackedSievePtr_4trait", i), i, |b, i| {
-            let table = black_box(make_fibonacci_packed_sieve::<_, 4, 3>());
-            let ptr = PackedSievePtr::new(&table);
-            b.iter(|| fibonacci(black_box(ptr), black_box(*i)))
+    }
+}
+
+fn bench_multiptr<const TRAIT_N: usize, const FUNC_N: usize>(group: &mut BenchmarkGroup<WallTime>) {
+    for n in NS.iter() {
+        group.bench_with_input(id("MultiVPtr", TRAIT_N, FUNC_N, *n), n, |b, n| {
+            let table = black_box(make_fibonacci_multiptr::<_, TRAIT_N, FUNC_N>());
+            let ptr = MultiVPtr::new(&table);
+            b.iter(|| fibonacci(black_box(ptr), black_box(*n)))
         });
-        group.bench_with_input(BenchmarkId::new("PackedSievePtr_5trait", i), i, |b, i| {
-            let table = black_box(make_fibonacci_packed_sieve::<_, 5, 2>());
+    }
+}
+
+fn bench_packed_sieve<const TRAIT_N: usize, const FUNC_N: usize>(
+    group: &mut BenchmarkGroup<WallTime>,
+) {
+    for n in NS.iter() {
+        group.bench_with_input(id("PackedSievePtr", TRAIT_N, FUNC_N, *n), n, |b, n| {
+            let table = black_box(make_fibonacci_packed_sieve::<_, TRAIT_N, FUNC_N>());
             let ptr = PackedSievePtr::new(&table);
-            b.iter(|| fibonacci(black_box(ptr), black_box(*i)))
+            b.iter(|| fibonacci(black_box(ptr), black_box(*n)))
         });
-        group.bench_with_input(BenchmarkId::new("InlineSievePtr", i), i, |b, i| {
-            let table = black_box(make_fibonacci_inline_sieve());
-            let ptr = InlineSievePtr::new(&table);
-            b.iter(|| fibonacci(black_box(ptr), black_box(*i)))
+    }
+}
+
+fn bench_rel_packed_sieve<const TRAIT_N: usize, const FUNC_N: usize>(
+    group: &mut BenchmarkGroup<WallTime>,
+) {
+    for n in NS.iter() {
+        group.bench_with_input(id("RelPackedSievePtr", TRAIT_N, FUNC_N, *n), n, |b, n| {
+            let table = black_box(make_fibonacci_rel_packed_sieve::<_, TRAIT_N, FUNC_N>());
+            let ptr = RelPackedSievePtr::new(&table);
+            b.iter(|| fibonacci(black_box(ptr), black_box(*n)))
         });
-        group.bench_with_input(BenchmarkId::new("VPtr", i), i, |b, i| {
-            let table = black_box(make_fibonacci_vtable());
-            let ptr = VPtr::new(&table);
-            b.iter(|| fibonacci(black_box(ptr), black_box(*i)))
+    }
+}
+
+fn bench_itab<const TRAIT_N: usize, const FUNC_N: usize>(group: &mut BenchmarkGroup<WallTime>) {
+    for n in NS.iter() {
+        group.bench_with_input(id("ItabPtr", TRAIT_N, FUNC_N, *n), n, |b, n| {
+            let cache = black_box(make_fibonacci_itab_cache::<_, TRAIT_N, FUNC_N>());
+            let ptr = ItabPtr::new(&cache, 0);
+            b.iter(|| fibonacci(black_box(ptr), black_box(*n)))
         });
-        group.bench_with_input(BenchmarkId::new("MultiVPtr_2trait", i), i, |b, i| {
-            let table = black_box(make_fibonacci_multiptr::<_, 2, 5>());
-            let ptr = MultiVPtr::new(&table);
-            b.iter(|| fibonacci(black_box(ptr), black_box(*i)))
+    }
+}
+
+fn bench_trait_search<const TRAIT_N: usize, const FUNC_N: usize>(
+    group: &mut BenchmarkGroup<WallTime>,
+) {
+    for n in NS.iter() {
+        group.bench_with_input(id("TraitSearchPtr", TRAIT_N, FUNC_N, *n), n, |b, n| {
+            let table = black_box(make_fibonacci_trait_search::<_, TRAIT_N, FUNC_N>());
+            let ptr = TraitSearchPtr::new(&table, fibonacci_trait_ids::<TRAIT_N>()).unwrap();
+            b.iter(|| fibonacci(black_box(ptr), black_box(*n)))
         });
-        group.bench_with_input(BenchmarkId::new("MultiVPtr_3trait", i), i, |b, i| {
-            let table = black_box(make_fibonacci_multiptr::<_, 3, 4>());
-            let ptr = MultiVPtr::new(&table);
-            b.iter(|| fibonacci(black_box(ptr), black_box(*i)))
+    }
+}
+
+fn bench_cpp<const TRAIT_N: usize, const FUNC_N: usize>(group: &mut BenchmarkGroup<WallTime>) {
+    for n in NS.iter() {
+        group.bench_with_input(id("CppPtr", TRAIT_N, FUNC_N, *n), n, |b, n| {
+            let table = black_box(make_fibonacci_cpp::<_, TRAIT_N, FUNC_N>());
+            let object = CppObject::new(&table);
+            let ptr = CppPtr::new(&object);
+            b.iter(|| fibonacci(black_box(ptr), black_box(*n)))
         });
-        group.bench_with_input(BenchmarkId::new("MultiVPtr_4trait", i), i, |b, i| {
-            let table = black_box(make_fibonacci_multiptr::<_, 4, 3>());
-            let ptr = MultiVPtr::new(&table);
-            b.iter(|| fibonacci(black_box(ptr), black_box(*i)))
+    }
+}
+
+fn bench_displacement<const TRAIT_N: usize, const FUNC_N: usize>(
+    group: &mut BenchmarkGroup<WallTime>,
+) {
+    for n in NS.iter() {
+        group.bench_with_input(id("DisplacementPtr", TRAIT_N, FUNC_N, *n), n, |b, n| {
+            let table = black_box(make_fibonacci_displacement::<_, TRAIT_N, FUNC_N>());
+            let ptr = DisplacementPtr::<_, _, FUNC_N>::new(&table, 0);
+            b.iter(|| fibonacci(black_box(ptr), black_box(*n)))
         });
-        group.bench_with_input(BenchmarkId::new("MultiVPtr_5trait", i), i, |b, i| {
-            let table = black_box(make_fibonacci_multiptr::<_, 5, 2>());
-            let ptr = MultiVPtr::new(&table);
-            b.iter(|| fibonacci(black_box(ptr), black_box(*i)))
+    }
+}
+
+fn bench_inline_sieve<const TRAIT_N: usize, const FUNC_N: usize, const N: usize>(
+    group: &mut BenchmarkGroup<WallTime>,
+) {
+    for n in NS.iter() {
+        group.bench_with_input(id("InlineSievePtr", TRAIT_N, FUNC_N, *n), n, |b, n| {
+            let table = black_box(make_fibonacci_inline_sieve_sized::<_, TRAIT_N, FUNC_N, N>());
+            let ptr = InlineSievePtr::new(&table);
+            b.iter(|| fibonacci(black_box(ptr), black_box(*n)))
         });
     }
+}
+
+/// The `n` of the README table.
+const README_N: u64 = 10;
+
+/// Benches `fibonacci` through `$new(&$make)` for `README_N`, as `$function_id`.
+macro_rules! bench_readme {
+    ($group:expr, $function_id:literal, $make:expr, $new:path) => {
+        $group.bench_with_input(
+            BenchmarkId::new($function_id, README_N),
+            &README_N,
+            |b, n| {
+                let table = black_box($make);
+                let ptr = $new(&table);
+                b.iter(|| fibonacci(black_box(ptr), black_box(*n)))
+            },
+        );
+    };
+}
+
+/// The configurations of the README table, with the IDs of its 2021 run.
+fn bench_readme(group: &mut BenchmarkGroup<WallTime>) {
+    bench_readme!(
+        group,
+        "PackedSievePtr_2trait",
+        make_fibonacci_packed_sieve::<_, 2, 5>(),
+        PackedSievePtr::new
+    );
+    bench_readme!(
+        group,
+        "PackedSievePtr_3trait",
+        make_fibonacci_packed_sieve::<_, 3, 4>(),
+        PackedSievePtr::new
+    );
+    bench_readme!(
+        group,
+        "PackedSievePtr_4trait",
+        make_fibonacci_packed_sieve::<_, 4, 3>(),
+        PackedSievePtr::new
+    );
+    bench_readme!(
+        group,
+        "PackedSievePtr_5trait",
+        make_fibonacci_packed_sieve::<_, 5, 2>(),
+        PackedSievePtr::new
+    );
+    bench_readme!(
+        group,
+        "InlineSievePtr",
+        make_fibonacci_inline_sieve(),
+        InlineSievePtr::new
+    );
+    bench_readme!(group, "VPtr", make_fibonacci_vtable(), VPtr::new);
+    bench_readme!(
+        group,
+        "MultiVPtr_2trait",
+        make_fibonacci_multiptr::<_, 2, 5>(),
+        MultiVPtr::new
+    );
+    bench_readme!(
+        group,
+        "MultiVPtr_3trait",
+        make_fibonacci_multiptr::<_, 3, 4>(),
+        MultiVPtr::new
+    );
+    bench_readme!(
+        group,
+        "MultiVPtr_4trait",
+        make_fibonacci_multiptr::<_, 4, 3>(),
+        MultiVPtr::new
+    );
+    bench_readme!(
+        group,
+        "MultiVPtr_5trait",
+        make_fibonacci_multiptr::<_, 5, 2>(),
+        MultiVPtr::new
+    );
+}
+
+/// Calls `$bench::<TRAIT_N, FUNC_N>` for every `(TRAIT_N, FUNC_N)` pair.
+macro_rules! sweep {
+    ($bench:ident, $group:expr, $(($trait_n:literal, $func_n:literal)),* $(,)?) => {
+        $($bench::<$trait_n, $func_n>($group);)*
+    };
+}
+
+/// Like `sweep!`, also passing the number of slots of the inline sieve table.
+macro_rules! sweep_inline_sieve {
+    ($group:expr, $(($trait_n:literal, $func_n:literal)),* $(,)?) => {
+        $(bench_inline_sieve::<$trait_n, $func_n, { $trait_n * ($func_n + 1) }>($group);)*
+    };
+}
+
+fn bench_fibs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Fibonacci");
+    bench_readme(&mut group);
+    if env::var_os("FIBONACCI_SWEEP").is_none() {
+        group.finish();
+        return;
+    }
+    bench_vptr::<8>(&mut group);
+    bench_vptr::<16>(&mut group);
+    bench_rel_vptr::<8>(&mut group);
+    bench_rel_vptr::<16>(&mut group);
+    multi_trait_matrix!(sweep!(bench_packed_sieve, &mut group,));
+    multi_trait_matrix!(sweep!(bench_rel_packed_sieve, &mut group,));
+    multi_trait_matrix!(sweep!(bench_multiptr, &mut group,), beyond_8);
+    multi_trait_matrix!(sweep_inline_sieve!(&mut group,), inline_sieve);
+    multi_trait_matrix!(sweep!(bench_itab, &mut group,), beyond_8);
+    multi_trait_matrix!(sweep!(bench_trait_search, &mut group,), beyond_8);
+    multi_trait_matrix!(sweep!(bench_cpp, &mut group,), beyond_8);
+    multi_trait_matrix!(sweep!(bench_displacement, &mut group,), beyond_8);
     group.finish();
 }
 
@@ -0,0 +1,83 @@
+//! Benches `HybridSievePtr` against `PackedSievePtr`, and `VPtr` as the baseline.
+//!
+//! The `primary` benches call `fibonacci_04`, which returns 1, through a pointer that only sees the
+//! trait of the method: it is the primary trait of the hybrid pointer, which should cost the same as
+//! `VPtr`. The `fibonacci_<n>` benches compute `fibonacci(n)`, calling the methods of every trait.
+//! Benchmark IDs are `Hybrid/<Representation>_T<TRAIT_N>_F<FUNC_N>/<primary or fibonacci_<n>>`.
+use std::{ptr::null, time::Duration};
+
+use bench_vtables::{
+    fibonacci::{fibonacci, make_fibonacci_packed_sieve, make_fibonacci_vtable_sized},
+    hybrid_sieve::HybridSievePtr,
+    packed_sieve_table::PackedSievePtr,
+    v_table::VPtr,
+    FuncTablePtr, ProjectPtr,
+};
+use criterion::{
+    black_box, criterion_group, criterion_main, measurement::WallTime, BenchmarkGroup, BenchmarkId,
+    Criterion,
+};
+
+/// The index of `fibonacci_04` among the fibonacci functions.
+const METHOD: usize = 4;
+
+/// The `n` to compute `fibonacci(n)` of.
+const N: u64 = 10;
+
+/// Benches a pointer, projected on the trait of `METHOD` for the `primary` bench.
+fn bench_ptr<P: ProjectPtr<Arg = u64, Ret = u64>>(
+    group: &mut BenchmarkGroup<WallTime>,
+    representation: &str,
+    ptr: P,
+    trait_n: usize,
+    func_n: usize,
+) {
+    let function_id = format!("{}_T{}_F{}", representation, trait_n, func_n);
+    let primary = unsafe { ptr.project(1 << (METHOD / func_n)) };
+    let trait_func_n = (METHOD % func_n) as u32;
+    group.bench_function(BenchmarkId::new(&function_id, "primary"), |b| {
+        b.iter(|| unsafe { black_box(primary).get_function(trait_func_n)(null(), black_box(0)) })
+    });
+    group.bench_function(
+        BenchmarkId::new(&function_id, format!("fibonacci_{}", N)),
+        |b| b.iter(|| fibonacci(black_box(ptr), black_box(N))),
+    );
+}
+
+fn bench_vptr<const FUNC_N: usize>(group: &mut BenchmarkGroup<WallTime>) {
+    let table = make_fibonacci_vtable_sized::<_, FUNC_N>();
+    let ptr = VPtr::new(&table);
+    let function_id = format!("VPtr_T1_F{}", FUNC_N);
+    group.bench_function(BenchmarkId::new(&function_id, "primary"), |b| {
+        b.iter(|| unsafe { black_box(ptr).get_function(METHOD as u32)(null(), black_box(0)) })
+    });
+    group.bench_function(
+        BenchmarkId::new(&function_id, format!("fibonacci_{}", N)),
+        |b| b.iter(|| fibonacci(black_box(ptr), black_box(N))),
+    );
+}
+
+fn bench_sieves<const TRAIT_N: usize, const FUNC_N: usize>(group: &mut BenchmarkGroup<WallTime>) {
+    let table = make_fibonacci_packed_sieve::<_, TRAIT_N, FUNC_N>();
+    let ptr = PackedSievePtr::new(&table);
+    bench_ptr(group, "PackedSievePtr", ptr, TRAIT_N, FUNC_N);
+    let table = make_fibonacci_packed_sieve::<_, TRAIT_N, FUNC_N>();
+    let ptr = HybridSievePtr::new(&table);
+    bench_ptr(group, "HybridSievePtr", ptr, TRAIT_N, FUNC_N);
+}
+
+fn bench_hybrid(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Hybrid");
+    bench_vptr::<8>(&mut group);
+    bench_sieves::<2, 4>(&mut group);
+    bench_sieves::<3, 3>(&mut group);
+    bench_sieves::<8, 1>(&mut group);
+    group.finish();
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default().measurement_time(Duration::from_secs(10));
+    targets = bench_hybrid
+);
+criterion_main!(benches);
@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use bench_vtables::{
+    inline_sieve_table::InlineSievePtr,
+    interpreter::{
+        interpret, make_interpreter_inline_sieve, make_interpreter_multiptr,
+        make_interpreter_packed_sieve, make_interpreter_vtable, random_tree, Kind,
+    },
+    multi_ptrs::MultiVPtr,
+    packed_sieve_table::PackedSievePtr,
+    v_table::VPtr,
+};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+fn bench_interpreter(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Interpreter");
+    for depth in [8u32, 12, 16].iter() {
+        group.bench_with_input(
+            BenchmarkId::new("PackedSievePtr", depth),
+            depth,
+            |b, depth| {
+                let tables = Kind::ALL.map(make_interpreter_packed_sieve);
+                let metas = [0, 1, 2, 3, 4].map(|k| PackedSievePtr::new(&tables[k]));
+                let tree = unsafe { random_tree(&metas, *depth, 42) };
+                b.iter(|| interpret(black_box(&*tree)))
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("InlineSievePtr", depth),
+            depth,
+            |b, depth| {
+                let tables = Kind::ALL.map(make_interpreter_inline_sieve);
+                let metas = [0, 1, 2, 3, 4].map(|k| InlineSievePtr::new(&tables[k]));
+                let tree = unsafe { random_tree(&metas, *depth, 42) };
+                b.iter(|| interpret(black_box(&*tree)))
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("VPtr", depth), depth, |b, depth| {
+            let tables = Kind::ALL.map(make_interpreter_vtable);
+            let metas = [0, 1, 2, 3, 4].map(|k| VPtr::new(&tables[k]));
+            let tree = unsafe { random_tree(&metas, *depth, 42) };
+            b.iter(|| interpret(black_box(&*tree)))
+        });
+        group.bench_with_input(BenchmarkId::new("MultiVPtr", depth), depth, |b, depth| {
+            let tables = Kind::ALL.map(make_interpreter_multiptr);
+            let metas = [0, 1, 2, 3, 4].map(|k| MultiVPtr::new(&tables[k]));
+            let tree = unsafe { random_tree(&metas, *depth, 42) };
+            b.iter(|| interpret(black_box(&*tree)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default().measurement_time(Duration::from_secs(10));
+    targets = bench_interpreter
+);
+criterion_main!(benches);
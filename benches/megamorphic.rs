@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use bench_vtables::{
+    displacement::DisplacementPtr,
+    inline_sieve_table::InlineSievePtr,
+    megamorphic::{
+        dispatch_all, make_megamorphic_displacement, make_megamorphic_inline_sieve,
+        make_megamorphic_multiptr, make_megamorphic_packed_sieve, make_megamorphic_vtable,
+        shuffled_tables, FUNC_N,
+    },
+    multi_ptrs::MultiVPtr,
+    packed_sieve_table::PackedSievePtr,
+    v_table::VPtr,
+};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// Number of calls per iteration, whatever the number of tables.
+const CALL_N: usize = 4096;
+
+fn bench_megamorphic(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Megamorphic");
+    for table_n in [16usize, 256, 1024].iter() {
+        let ids = shuffled_tables(*table_n, CALL_N, 42);
+        group.bench_with_input(
+            BenchmarkId::new("PackedSievePtr", table_n),
+            &ids,
+            |b, ids| {
+                let tables: Vec<_> = (0..*table_n).map(make_megamorphic_packed_sieve).collect();
+                let objects: Vec<_> = ids
+                    .iter()
+                    .map(|id| PackedSievePtr::new(&tables[*id]))
+                    .collect();
+                b.iter(|| unsafe { dispatch_all(black_box(&objects), black_box(3)) })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("InlineSievePtr", table_n),
+            &ids,
+            |b, ids| {
+                let tables: Vec<_> = (0..*table_n).map(make_megamorphic_inline_sieve).collect();
+                let objects: Vec<_> = ids
+                    .iter()
+                    .map(|id| InlineSievePtr::new(&tables[*id]))
+                    .collect();
+                b.iter(|| unsafe { dispatch_all(black_box(&objects), black_box(3)) })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("VPtr", table_n), &ids, |b, ids| {
+            let tables: Vec<_> = (0..*table_n).map(make_megamorphic_vtable).collect();
+            let objects: Vec<_> = ids.iter().map(|id| VPtr::new(&tables[*id])).collect();
+            b.iter(|| unsafe { dispatch_all(black_box(&objects), black_box(3)) })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("DisplacementPtr", table_n),
+            &ids,
+            |b, ids| {
+                let table = make_megamorphic_displacement(*table_n);
+                let objects: Vec<_> = ids
+                    .iter()
+                    .map(|id| DisplacementPtr::<_, _, FUNC_N>::new(&table, *id as u32))
+                    .collect();
+                b.iter(|| unsafe { dispatch_all(black_box(&objects), black_box(3)) })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("MultiVPtr", table_n), &ids, |b, ids| {
+            let tables: Vec<_> = (0..*table_n).map(make_megamorphic_multiptr).collect();
+            let objects: Vec<_> = ids.iter().map(|id| MultiVPtr::new(&tables[*id])).collect();
+            b.iter(|| unsafe { dispatch_all(black_box(&objects), black_box(3)) })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default().measurement_time(Duration::from_secs(10));
+    targets = bench_megamorphic
+);
+criterion_main!(benches);
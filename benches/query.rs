@@ -0,0 +1,125 @@
+//! Benches asking a pointer for a trait by id, for the representations that support it.
+//!
+//! The others can't: see `QueryPtr` for why.
+//!
+//! The pointer asks for the last trait of its concrete type, or for a trait the concrete type
+//! does not implement. Benchmark IDs are `Query/<Representation>_T<TRAIT_N>_F<FUNC_N>/<found or
+//! missing>`.
+use std::time::Duration;
+
+use bench_vtables::{
+    fibonacci::{
+        fibonacci_trait_ids, make_fibonacci_inline_sieve_sized, make_fibonacci_packed_sieve,
+        make_fibonacci_rel_packed_sieve, make_fibonacci_split_sieve,
+    },
+    hybrid_sieve::HybridSievePtr,
+    inline_sieve_table::InlineSievePtr,
+    packed_sieve_table::PackedSievePtr,
+    rel_packed_sieve_table::RelPackedSievePtr,
+    split_sieve::SplitSievePtr,
+    QueryPtr, TraitId,
+};
+use criterion::{
+    black_box, criterion_group, criterion_main, measurement::WallTime, BenchmarkGroup, BenchmarkId,
+    Criterion,
+};
+
+/// No fibonacci trait has an even id.
+const MISSING: TraitId = 2;
+
+fn bench_query<P: QueryPtr>(
+    group: &mut BenchmarkGroup<WallTime>,
+    function_id: &str,
+    ptr: P,
+    found: TraitId,
+) {
+    for (parameter, trait_id) in [("found", found), ("missing", MISSING)].iter() {
+        group.bench_with_input(
+            BenchmarkId::new(function_id, parameter),
+            trait_id,
+            |b, trait_id| b.iter(|| black_box(ptr).query(black_box(*trait_id)).is_some()),
+        );
+    }
+}
+
+fn bench_packed_sieve<const TRAIT_N: usize, const FUNC_N: usize>(
+    group: &mut BenchmarkGroup<WallTime>,
+) {
+    let table = make_fibonacci_packed_sieve::<_, TRAIT_N, FUNC_N>();
+    let ptr = PackedSievePtr::new(&table);
+    let found = fibonacci_trait_ids::<TRAIT_N>()[TRAIT_N - 1];
+    let id = format!("PackedSievePtr_T{}_F{}", TRAIT_N, FUNC_N);
+    bench_query(group, &id, ptr, found);
+}
+
+fn bench_rel_packed_sieve<const TRAIT_N: usize, const FUNC_N: usize>(
+    group: &mut BenchmarkGroup<WallTime>,
+) {
+    let table = make_fibonacci_rel_packed_sieve::<_, TRAIT_N, FUNC_N>();
+    let ptr = RelPackedSievePtr::new(&table);
+    let found = fibonacci_trait_ids::<TRAIT_N>()[TRAIT_N - 1];
+    let id = format!("RelPackedSievePtr_T{}_F{}", TRAIT_N, FUNC_N);
+    bench_query(group, &id, ptr, found);
+}
+
+fn bench_hybrid_sieve<const TRAIT_N: usize, const FUNC_N: usize>(
+    group: &mut BenchmarkGroup<WallTime>,
+) {
+    let table = make_fibonacci_packed_sieve::<_, TRAIT_N, FUNC_N>();
+    let ptr = HybridSievePtr::new(&table);
+    let found = fibonacci_trait_ids::<TRAIT_N>()[TRAIT_N - 1];
+    let id = format!("HybridSievePtr_T{}_F{}", TRAIT_N, FUNC_N);
+    bench_query(group, &id, ptr, found);
+}
+
+/// Aligned for the 4 bits of the sieve a `SplitSievePtr` of 8 traits puts in its data pointer.
+#[repr(align(16))]
+struct Object;
+
+fn bench_split_sieve<const TRAIT_N: usize, const FUNC_N: usize>(
+    group: &mut BenchmarkGroup<WallTime>,
+) {
+    let table = make_fibonacci_split_sieve::<_, TRAIT_N, FUNC_N>();
+    // Querying doesn't read the object.
+    let ptr = SplitSievePtr::new(&Object, &table);
+    let found = fibonacci_trait_ids::<TRAIT_N>()[TRAIT_N - 1];
+    let id = format!("SplitSievePtr_T{}_F{}", TRAIT_N, FUNC_N);
+    bench_query(group, &id, ptr, found);
+}
+
+fn bench_inline_sieve<const TRAIT_N: usize, const FUNC_N: usize, const N: usize>(
+    group: &mut BenchmarkGroup<WallTime>,
+) {
+    let table = make_fibonacci_inline_sieve_sized::<_, TRAIT_N, FUNC_N, N>();
+    let ptr = InlineSievePtr::new(&table);
+    let found = fibonacci_trait_ids::<TRAIT_N>()[TRAIT_N - 1];
+    let id = format!("InlineSievePtr_T{}_F{}", TRAIT_N, FUNC_N);
+    bench_query(group, &id, ptr, found);
+}
+
+fn bench_queries(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Query");
+    bench_packed_sieve::<2, 4>(&mut group);
+    bench_packed_sieve::<4, 2>(&mut group);
+    bench_packed_sieve::<8, 1>(&mut group);
+    bench_inline_sieve::<2, 4, 10>(&mut group);
+    bench_inline_sieve::<4, 2, 12>(&mut group);
+    bench_inline_sieve::<8, 1, 16>(&mut group);
+    bench_rel_packed_sieve::<2, 4>(&mut group);
+    bench_rel_packed_sieve::<4, 2>(&mut group);
+    bench_rel_packed_sieve::<8, 1>(&mut group);
+    bench_hybrid_sieve::<2, 4>(&mut group);
+    bench_hybrid_sieve::<4, 2>(&mut group);
+    bench_hybrid_sieve::<8, 1>(&mut group);
+    bench_split_sieve::<2, 4>(&mut group);
+    bench_split_sieve::<4, 2>(&mut group);
+    bench_split_sieve::<8, 1>(&mut group);
+    group.finish();
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default().measurement_time(Duration::from_secs(10));
+    targets = bench_queries
+);
+criterion_main!(benches);
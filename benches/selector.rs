@@ -0,0 +1,67 @@
+//! Benches `fibonacci` as the number of methods grows, for `PerfectHashPtr` and `InlineSievePtr`.
+//!
+//! Each trait has 4 methods, the fibonacci functions filling the first 2 traits and fillers the
+//! others. Benchmark IDs are `Selector/<Representation>/<number of methods>`.
+use std::time::Duration;
+
+use bench_vtables::{
+    fibonacci::{fibonacci, make_fibonacci_inline_sieve_sized, make_fibonacci_perfect_hash_sized},
+    inline_sieve_table::InlineSievePtr,
+    perfect_hash::PerfectHashPtr,
+};
+use criterion::{
+    black_box, criterion_group, criterion_main, measurement::WallTime, BenchmarkGroup, BenchmarkId,
+    Criterion,
+};
+
+const FUNC_N: usize = 4;
+const N: u64 = 10;
+
+fn bench_perfect_hash<const TRAIT_N: usize, const SLOTS: usize>(
+    group: &mut BenchmarkGroup<WallTime>,
+) {
+    let id = BenchmarkId::new("PerfectHashPtr", TRAIT_N * FUNC_N);
+    group.bench_function(id, |b| {
+        let table = black_box(make_fibonacci_perfect_hash_sized::<
+            PerfectHashPtr<_, _, FUNC_N, SLOTS>,
+            TRAIT_N,
+            FUNC_N,
+            SLOTS,
+        >());
+        let ptr = PerfectHashPtr::new(&table);
+        b.iter(|| fibonacci(black_box(ptr), black_box(N)))
+    });
+}
+
+fn bench_inline_sieve<const TRAIT_N: usize, const SLOTS: usize>(
+    group: &mut BenchmarkGroup<WallTime>,
+) {
+    let id = BenchmarkId::new("InlineSievePtr", TRAIT_N * FUNC_N);
+    group.bench_function(id, |b| {
+        let table = black_box(make_fibonacci_inline_sieve_sized::<_, TRAIT_N, FUNC_N, SLOTS>());
+        let ptr = InlineSievePtr::new(&table);
+        b.iter(|| fibonacci(black_box(ptr), black_box(N)))
+    });
+}
+
+fn bench_selector(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Selector");
+    // Twice as many slots as methods or more, so that a perfect hash is quick to find.
+    bench_perfect_hash::<2, 16>(&mut group);
+    bench_perfect_hash::<4, 32>(&mut group);
+    bench_perfect_hash::<8, 64>(&mut group);
+    bench_perfect_hash::<12, 128>(&mut group);
+    // `InlineSievePtr` supports at most 64 slots, a method plus one per trait.
+    bench_inline_sieve::<2, { 2 * (FUNC_N + 1) }>(&mut group);
+    bench_inline_sieve::<4, { 4 * (FUNC_N + 1) }>(&mut group);
+    bench_inline_sieve::<8, { 8 * (FUNC_N + 1) }>(&mut group);
+    bench_inline_sieve::<12, { 12 * (FUNC_N + 1) }>(&mut group);
+    group.finish();
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default().measurement_time(Duration::from_secs(10));
+    targets = bench_selector
+);
+criterion_main!(benches);
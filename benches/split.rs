@@ -0,0 +1,96 @@
+//! Benches `SplitSievePtr` against a `SumBox` of a `PackedSievePtr`, both two words.
+//!
+//! The functions read a field of the object their data pointer points to, so the split pointer has
+//! to strip its sieve from the data pointer before each call. The `all` benches call the method of
+//! every trait, the `last` benches call the method of the last trait through a pointer projected
+//! on it, which is in the data pointer of `SplitSievePtr` beyond `TABLE_BITS` traits. Benchmark
+//! IDs are `Split/<Representation>_T<TRAIT_N>_F<FUNC_N>/<all or last>`.
+use std::time::Duration;
+
+use bench_vtables::{
+    packed_sieve_table::{PackedSievePtr, PackedSieveTable},
+    split_sieve::{SplitSievePtr, SplitSieveTable},
+    sum_box::SumBox,
+    ProjectPtr,
+};
+use criterion::{
+    black_box, criterion_group, criterion_main, measurement::WallTime, BenchmarkGroup, BenchmarkId,
+    Criterion,
+};
+
+/// The object: aligned for the 4 bits of the sieve `SplitSievePtr` puts in the data pointer with 8
+/// traits.
+#[repr(C, align(16))]
+struct Fields([u64; 8]);
+
+/// Adds the `K`-th field of the object to `x`.
+fn field<const K: usize>(data: *const (), x: u64) -> u64 {
+    x + unsafe { (*(data as *const Fields)).0[K] }
+}
+
+/// The functions of the tables, one per field.
+fn fields_funcs() -> [fn(*const (), u64) -> u64; 8] {
+    [
+        field::<0>, field::<1>, field::<2>, field::<3>, field::<4>, field::<5>, field::<6>,
+        field::<7>,
+    ]
+}
+
+fn new_fields() -> Fields {
+    Fields([1, 2, 3, 4, 5, 6, 7, 8])
+}
+
+fn bench_packed_sieve<const TRAIT_N: usize, const FUNC_N: usize>(
+    group: &mut BenchmarkGroup<WallTime>,
+) {
+    let table = PackedSieveTable::<_, TRAIT_N, FUNC_N>::new(&fields_funcs());
+    let object = unsafe { SumBox::new(new_fields(), PackedSievePtr::new(&table)) };
+    let function_id = format!("PackedSievePtr_T{}_F{}", TRAIT_N, FUNC_N);
+    group.bench_function(BenchmarkId::new(&function_id, "all"), |b| {
+        b.iter(|| {
+            let object = black_box(&object);
+            (0..8).fold(0, |acc, n| unsafe { object.call(n, acc) })
+        })
+    });
+    let last = unsafe { object.project(1 << (TRAIT_N - 1)) };
+    group.bench_function(BenchmarkId::new(&function_id, "last"), |b| {
+        b.iter(|| unsafe { black_box(&last).call(0, black_box(0)) })
+    });
+}
+
+fn bench_split_sieve<const TRAIT_N: usize, const FUNC_N: usize>(
+    group: &mut BenchmarkGroup<WallTime>,
+) {
+    let table = SplitSieveTable::<_, TRAIT_N, FUNC_N>::new(&fields_funcs());
+    let object = Box::new(new_fields());
+    let ptr = SplitSievePtr::new(&*object, &table);
+    let function_id = format!("SplitSievePtr_T{}_F{}", TRAIT_N, FUNC_N);
+    group.bench_function(BenchmarkId::new(&function_id, "all"), |b| {
+        b.iter(|| {
+            let ptr = black_box(ptr);
+            (0..8).fold(0, |acc, n| unsafe { ptr.call(n, acc) })
+        })
+    });
+    let last = unsafe { ptr.project(1 << (TRAIT_N - 1)) };
+    group.bench_function(BenchmarkId::new(&function_id, "last"), |b| {
+        b.iter(|| unsafe { black_box(last).call(0, black_box(0)) })
+    });
+}
+
+fn bench_split(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Split");
+    bench_packed_sieve::<2, 4>(&mut group);
+    bench_split_sieve::<2, 4>(&mut group);
+    bench_packed_sieve::<4, 2>(&mut group);
+    bench_split_sieve::<4, 2>(&mut group);
+    bench_packed_sieve::<8, 1>(&mut group);
+    bench_split_sieve::<8, 1>(&mut group);
+    group.finish();
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default().measurement_time(Duration::from_secs(10));
+    targets = bench_split
+);
+criterion_main!(benches);
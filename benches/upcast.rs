@@ -0,0 +1,90 @@
+//! Benches converting a pointer to a sum of traits to a pointer to one of them, then calling a
+//! method through the result.
+//!
+//! The method is `fibonacci_04`, which returns 1, so that the conversion and the call dominate. It
+//! does not read the object, so the benches pass no data.
+//! Benchmark IDs are `Upcast/<Representation>_T<TRAIT_N>_F<FUNC_N>`.
+use std::{ptr::null, time::Duration};
+
+use bench_vtables::{
+    cpp_table::{CppObject, CppPtr},
+    fibonacci::{make_fibonacci_cpp, make_fibonacci_multiptr, make_fibonacci_packed_sieve},
+    multi_ptrs::MultiVPtr,
+    packed_sieve_table::PackedSievePtr,
+    FuncTablePtr, ProjectPtr,
+};
+use criterion::{
+    black_box, criterion_group, criterion_main, measurement::WallTime, BenchmarkGroup, Criterion,
+};
+
+/// The index of `fibonacci_04` among the fibonacci functions.
+const METHOD: usize = 4;
+
+fn id(representation: &str, trait_n: usize, func_n: usize) -> String {
+    format!("{}_T{}_F{}", representation, trait_n, func_n)
+}
+
+fn bench_packed_sieve<const TRAIT_N: usize, const FUNC_N: usize>(
+    group: &mut BenchmarkGroup<WallTime>,
+) {
+    let (trait_n, trait_func_n) = (METHOD / FUNC_N, (METHOD % FUNC_N) as u32);
+    let table = make_fibonacci_packed_sieve::<_, TRAIT_N, FUNC_N>();
+    let ptr = PackedSievePtr::new(&table);
+    group.bench_function(id("PackedSievePtr", TRAIT_N, FUNC_N), |b| {
+        b.iter(|| unsafe {
+            let ptr = black_box(ptr).project(1 << trait_n);
+            ptr.get_function(trait_func_n)(null(), black_box(0))
+        })
+    });
+}
+
+fn bench_multiptr<const TRAIT_N: usize, const FUNC_N: usize>(group: &mut BenchmarkGroup<WallTime>) {
+    let (trait_n, trait_func_n) = ((METHOD / FUNC_N) as u32, (METHOD % FUNC_N) as u32);
+    let table = make_fibonacci_multiptr::<_, TRAIT_N, FUNC_N>();
+    let ptr = MultiVPtr::new(&table);
+    group.bench_function(id("MultiVPtr", TRAIT_N, FUNC_N), |b| {
+        b.iter(|| unsafe {
+            let ptr = black_box(ptr);
+            ptr.get_trait_function(trait_n, trait_func_n)(null(), black_box(0))
+        })
+    });
+}
+
+fn bench_cpp<const TRAIT_N: usize, const FUNC_N: usize>(group: &mut BenchmarkGroup<WallTime>) {
+    let (trait_n, trait_func_n) = ((METHOD / FUNC_N) as u32, (METHOD % FUNC_N) as u32);
+    let table = make_fibonacci_cpp::<_, TRAIT_N, FUNC_N>();
+    let object = CppObject::new(&table);
+    let ptr = CppPtr::new(&object);
+    group.bench_function(id("CppPtr", TRAIT_N, FUNC_N), |b| {
+        b.iter(|| unsafe {
+            black_box(ptr)
+                .upcast(trait_n)
+                .call(trait_func_n, null(), black_box(0))
+        })
+    });
+}
+
+/// Calls `$bench::<TRAIT_N, FUNC_N>` for every `(TRAIT_N, FUNC_N)` pair.
+macro_rules! sweep {
+    ($bench:ident, $group:expr) => {
+        $bench::<2, 4>($group);
+        $bench::<3, 3>($group);
+        $bench::<4, 2>($group);
+        $bench::<8, 1>($group);
+    };
+}
+
+fn bench_upcast(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Upcast");
+    sweep!(bench_packed_sieve, &mut group);
+    sweep!(bench_multiptr, &mut group);
+    sweep!(bench_cpp, &mut group);
+    group.finish();
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default().measurement_time(Duration::from_secs(10));
+    targets = bench_upcast
+);
+criterion_main!(benches);
@@ -0,0 +1,105 @@
+//! Probe functions isolating the dispatch sequence of every representation.
+//!
+//! `tests/dispatch_size.rs` builds this example in release mode and counts the instructions of
+//! every `probe_*` symbol with `objdump`. The functions are `#[no_mangle]` so that they are easy to
+//! find, and `#[inline(never)]` so that they stay functions.
+//!
+//! The `_0` and `_6` variants use a constant index, as real call sites do; the others take the
+//! index as a parameter.
+use std::hint::black_box;
+
+use bench_vtables::{
+    hybrid_sieve::HybridSievePtr, inline_sieve_table::InlineSievePtr, multi_ptrs::MultiVPtr,
+    packed_sieve_table::PackedSievePtr, split_sieve::SplitSievePtr, v_table::VPtr, FuncTablePtr,
+};
+
+type VPtrT = VPtr<'static, u64, u64, 8>;
+type MultiVPtrT = MultiVPtr<'static, u64, u64, 3, 3>;
+type PackedSievePtrT = PackedSievePtr<'static, u64, u64, 3, 3>;
+type InlineSievePtrT = InlineSievePtr<'static, u64, u64, 12>;
+type HybridSievePtrT = HybridSievePtr<'static, u64, u64, 3, 3>;
+type SplitSievePtrT = SplitSievePtr<'static, u64, u64, 6, 2>;
+
+/// Declares the probes of a representation.
+macro_rules! probes {
+    ($ptr:ty, $any:ident, $first:ident, $seventh:ident) => {
+        #[no_mangle]
+        #[inline(never)]
+        pub fn $any(ptr: $ptr, n: u32) -> fn(*const (), u64) -> u64 {
+            unsafe { ptr.get_function(n) }
+        }
+
+        #[no_mangle]
+        #[inline(never)]
+        pub fn $first(ptr: $ptr) -> fn(*const (), u64) -> u64 {
+            unsafe { ptr.get_function(0) }
+        }
+
+        #[no_mangle]
+        #[inline(never)]
+        pub fn $seventh(ptr: $ptr) -> fn(*const (), u64) -> u64 {
+            unsafe { ptr.get_function(6) }
+        }
+    };
+}
+
+probes!(
+    VPtrT,
+    probe_vptr_get_function,
+    probe_vptr_get_function_0,
+    probe_vptr_get_function_6
+);
+probes!(
+    MultiVPtrT,
+    probe_multi_vptr_get_function,
+    probe_multi_vptr_get_function_0,
+    probe_multi_vptr_get_function_6
+);
+probes!(
+    PackedSievePtrT,
+    probe_packed_sieve_get_function,
+    probe_packed_sieve_get_function_0,
+    probe_packed_sieve_get_function_6
+);
+probes!(
+    InlineSievePtrT,
+    probe_inline_sieve_get_function,
+    probe_inline_sieve_get_function_0,
+    probe_inline_sieve_get_function_6
+);
+probes!(
+    HybridSievePtrT,
+    probe_hybrid_sieve_get_function,
+    probe_hybrid_sieve_get_function_0,
+    probe_hybrid_sieve_get_function_6
+);
+probes!(
+    SplitSievePtrT,
+    probe_split_sieve_get_function,
+    probe_split_sieve_get_function_0,
+    probe_split_sieve_get_function_6
+);
+
+fn main() {
+    // Keeps the probes alive in the executable.
+    black_box([
+        probe_vptr_get_function as *const (),
+        probe_vptr_get_function_0 as *const (),
+        probe_vptr_get_function_6 as *const (),
+        probe_multi_vptr_get_function as *const (),
+        probe_multi_vptr_get_function_0 as *const (),
+        probe_multi_vptr_get_function_6 as *const (),
+        probe_packed_sieve_get_function as *const (),
+        probe_packed_sieve_get_function_0 as *const (),
+        probe_packed_sieve_get_function_6 as *const (),
+        probe_inline_sieve_get_function as *const (),
+        probe_inline_sieve_get_function_0 as *const (),
+        probe_inline_sieve_get_function_6 as *const (),
+        probe_hybrid_sieve_get_function as *const (),
+        probe_hybrid_sieve_get_function_0 as *const (),
+        probe_hybrid_sieve_get_function_6 as *const (),
+        probe_split_sieve_get_function as *const (),
+        probe_split_sieve_get_function_0 as *const (),
+        probe_split_sieve_get_function_6 as *const (),
+    ]);
+}
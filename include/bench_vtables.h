@@ -0,0 +1,63 @@
+/* Generated by `cargo run --bin c_header`, do not edit. */
+#ifndef BENCH_VTABLES_H
+#define BENCH_VTABLES_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/* A function of a table. It has the Rust ABI: copy and compare it, don't call it. */
+typedef const void *bv_function;
+typedef uint32_t bv_trait_id;
+#define BV_NO_TRAIT_ID UINT32_MAX
+
+#define BV_VTABLE(N) struct { bv_function funcs[N]; }
+#define BV_MULTI_VTABLE(T, F) struct { bv_function tables[T][F]; }
+/* Followed by fields only Rust can read. */
+#define BV_PACKED_SIEVE_TABLE(T, F) \
+    struct { _Alignas(256) const bv_function *traits_ptr[T]; bv_trait_id trait_ids[T]; }
+/* Followed by fields only Rust can read. */
+#define BV_INLINE_SIEVE_TABLE(N) struct { bv_function funcs[N]; uintptr_t default_sieve; }
+
+#define BV_VPTR(N) struct { const bv_function *table; }
+#define BV_MULTI_VPTR(T, F) struct { const bv_function *ptrs[T]; }
+/* The table, with the sieve in its low 8 bits. */
+#define BV_PACKED_SIEVE_PTR(T, F) struct { uintptr_t multi_v_mangled; }
+#define BV_PACKED_SIEVE_MASK ((uintptr_t)0xff)
+/* The table, whose funcs come first. */
+#define BV_INLINE_SIEVE_PTR(N) struct { uintptr_t sieve; const bv_function *table; }
+
+typedef BV_VTABLE(8) bv_vtable_8;
+_Static_assert(sizeof(bv_vtable_8) == 8 * sizeof(void *), "bv_vtable_8");
+_Static_assert(offsetof(bv_vtable_8, funcs) == 0, "bv_vtable_8.funcs");
+
+typedef BV_MULTI_VTABLE(3, 3) bv_multi_vtable_3_3;
+_Static_assert(sizeof(bv_multi_vtable_3_3) == 9 * sizeof(void *), "bv_multi_vtable_3_3");
+_Static_assert(offsetof(bv_multi_vtable_3_3, tables) == 0, "bv_multi_vtable_3_3.tables");
+
+typedef BV_PACKED_SIEVE_TABLE(3, 3) bv_packed_sieve_table_3_3;
+_Static_assert(offsetof(bv_packed_sieve_table_3_3, traits_ptr) == 0, "bv_packed_sieve_table_3_3.traits_ptr");
+_Static_assert(offsetof(bv_packed_sieve_table_3_3, trait_ids) == 3 * sizeof(void *), "bv_packed_sieve_table_3_3.trait_ids");
+_Static_assert(_Alignof(bv_packed_sieve_table_3_3) == 256, "bv_packed_sieve_table_3_3");
+
+typedef BV_INLINE_SIEVE_TABLE(12) bv_inline_sieve_table_12;
+_Static_assert(offsetof(bv_inline_sieve_table_12, funcs) == 0, "bv_inline_sieve_table_12.funcs");
+_Static_assert(offsetof(bv_inline_sieve_table_12, default_sieve) == 12 * sizeof(void *), "bv_inline_sieve_table_12.default_sieve");
+
+typedef BV_VPTR(8) bv_vptr_8;
+_Static_assert(sizeof(bv_vptr_8) == sizeof(void *), "bv_vptr_8");
+_Static_assert(offsetof(bv_vptr_8, table) == 0, "bv_vptr_8.table");
+
+typedef BV_MULTI_VPTR(3, 3) bv_multi_vptr_3_3;
+_Static_assert(sizeof(bv_multi_vptr_3_3) == 3 * sizeof(void *), "bv_multi_vptr_3_3");
+_Static_assert(offsetof(bv_multi_vptr_3_3, ptrs) == 0, "bv_multi_vptr_3_3.ptrs");
+
+typedef BV_PACKED_SIEVE_PTR(3, 3) bv_packed_sieve_ptr_3_3;
+_Static_assert(sizeof(bv_packed_sieve_ptr_3_3) == sizeof(void *), "bv_packed_sieve_ptr_3_3");
+_Static_assert(offsetof(bv_packed_sieve_ptr_3_3, multi_v_mangled) == 0, "bv_packed_sieve_ptr_3_3.multi_v_mangled");
+
+typedef BV_INLINE_SIEVE_PTR(12) bv_inline_sieve_ptr_12;
+_Static_assert(sizeof(bv_inline_sieve_ptr_12) == 2 * sizeof(void *), "bv_inline_sieve_ptr_12");
+_Static_assert(offsetof(bv_inline_sieve_ptr_12, sieve) == 0, "bv_inline_sieve_ptr_12.sieve");
+_Static_assert(offsetof(bv_inline_sieve_ptr_12, table) == sizeof(void *), "bv_inline_sieve_ptr_12.table");
+
+#endif /* BENCH_VTABLES_H */
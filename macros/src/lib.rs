@@ -0,0 +1,218 @@
+//! `#[table_trait]`, which generates the tables of `bench_vtables` from an ordinary Rust trait.
+//!
+//! Re-exported as `bench_vtables::table_trait`, see there.
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, spanned::Spanned, AttributeArgs, Error, FnArg, Ident, ItemTrait, Lit, Meta,
+    NestedMeta, ReturnType, Signature, TraitItem, Type,
+};
+
+/// The arguments of the attribute.
+struct Args {
+    id: Lit,
+    marker: Option<Ident>,
+}
+
+fn parse_args(args: AttributeArgs) -> syn::Result<Args> {
+    let mut id = None;
+    let mut marker = None;
+    for arg in args {
+        match &arg {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("id") => {
+                id = Some(nv.lit.clone());
+            }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("marker") => match &nv.lit {
+                Lit::Str(name) => marker = Some(name.parse()?),
+                lit => return Err(Error::new_spanned(lit, "expected the name of the marker")),
+            },
+            _ => {
+                return Err(Error::new_spanned(
+                    arg,
+                    "expected `id = …` or `marker = \"…\"`",
+                ))
+            }
+        }
+    }
+    let id = id.ok_or_else(|| Error::new(Span::call_site(), "missing `id = …`"))?;
+    Ok(Args { id, marker })
+}
+
+/// A method, lowered to the function type of the tables.
+struct Method {
+    sig: Signature,
+    /// `arg0`, `arg1`… the names the generated code gives the arguments.
+    args: Vec<Ident>,
+    arg_types: Vec<Type>,
+}
+
+impl Method {
+    fn new(sig: &Signature) -> syn::Result<Self> {
+        let receiver = match sig.inputs.first() {
+            Some(FnArg::Receiver(receiver)) => receiver,
+            _ => return Err(Error::new_spanned(sig, "table methods take `&self`")),
+        };
+        if receiver.reference.is_none() || receiver.mutability.is_some() {
+            return Err(Error::new_spanned(receiver, "table methods take `&self`"));
+        }
+        if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+            return Err(Error::new_spanned(
+                &sig.generics,
+                "table methods can't be generic",
+            ));
+        }
+        if sig.asyncness.is_some() || sig.unsafety.is_some() || sig.variadic.is_some() {
+            return Err(Error::new_spanned(sig, "table methods are plain `fn`s"));
+        }
+        let mut sig = sig.clone();
+        let mut args = Vec::new();
+        let mut arg_types = Vec::new();
+        for (n, input) in sig.inputs.iter_mut().skip(1).enumerate() {
+            if let FnArg::Typed(pat_type) = input {
+                let arg = format_ident!("arg{}", n);
+                *pat_type.pat = syn::parse_quote!(#arg);
+                args.push(arg);
+                arg_types.push((*pat_type.ty).clone());
+            }
+        }
+        Ok(Self {
+            sig,
+            args,
+            arg_types,
+        })
+    }
+
+    /// The argument of the function in the tables: `()`, the argument or a tuple of them.
+    fn arg_type(&self) -> TokenStream2 {
+        match self.arg_types.as_slice() {
+            [ty] => quote!(#ty),
+            types => quote!((#(#types,)*)),
+        }
+    }
+
+    /// Like `arg_type`, for the values.
+    fn arg(&self) -> TokenStream2 {
+        match self.args.as_slice() {
+            [arg] => quote!(#arg),
+            args => quote!((#(#args,)*)),
+        }
+    }
+
+    fn ret_type(&self) -> TokenStream2 {
+        match &self.sig.output {
+            ReturnType::Default => quote!(()),
+            ReturnType::Type(_, ty) => quote!(#ty),
+        }
+    }
+}
+
+fn expand(args: Args, item: ItemTrait) -> syn::Result<TokenStream2> {
+    if !item.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &item.generics,
+            "table traits can't be generic",
+        ));
+    }
+    let methods = item
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            TraitItem::Method(method) => Some(Method::new(&method.sig)),
+            _ => None,
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    let first = methods
+        .first()
+        .ok_or_else(|| Error::new_spanned(&item.ident, "table traits have methods"))?;
+    let (arg_type, ret_type) = (first.arg_type(), first.ret_type());
+    for method in &methods[1..] {
+        if method.arg_type().to_string() != arg_type.to_string()
+            || method.ret_type().to_string() != ret_type.to_string()
+        {
+            return Err(Error::new(
+                method.sig.span(),
+                "the methods of a table trait have the same argument and return types",
+            ));
+        }
+    }
+
+    let vis = &item.vis;
+    let name = &item.ident;
+    let marker = args
+        .marker
+        .unwrap_or_else(|| format_ident!("{}Marker", item.ident));
+    let id = &args.id;
+    let func_n = methods.len();
+    let slots = methods.iter().map(|method| {
+        format_ident!(
+            "{}",
+            method.sig.ident.to_string().to_uppercase(),
+            span = method.sig.ident.span()
+        )
+    });
+    let indices = 0..func_n;
+    let thunks = methods.iter().map(|method| {
+        let method_name = &method.sig.ident;
+        let arg = method.arg();
+        let args = &method.args;
+        quote! {
+            |data: *const (), #arg| unsafe { &*(data as *const T) }.#method_name(#(#args),*)
+        }
+    });
+    let shims = methods.iter().enumerate().map(|(n, method)| {
+        let sig = &method.sig;
+        let arg = method.arg();
+        quote! {
+            #sig {
+                let function = self.meta().method::<#marker, #n>();
+                function(self.as_ptr() as *const (), #arg)
+            }
+        }
+    });
+    let marker_doc = format!(
+        "Stands for `{}` in the traits of a `Typed` pointer. Its constants are the slots of the methods.",
+        name
+    );
+
+    Ok(quote! {
+        #item
+
+        #[doc = #marker_doc]
+        #vis struct #marker;
+
+        impl #marker {
+            #(pub const #slots: usize = #indices;)*
+        }
+
+        impl ::bench_vtables::typed::TraitMarker for #marker {
+            const ID: ::bench_vtables::TraitId = #id;
+            const FUNC_N: usize = #func_n;
+        }
+
+        impl<T: #name> ::bench_vtables::typed::TraitFunctions<T> for #marker {
+            type Arg = #arg_type;
+            type Ret = #ret_type;
+            const FUNCTIONS: &'static [::bench_vtables::typed::MethodFn<#arg_type, #ret_type>] =
+                &[#(#thunks),*];
+        }
+
+        impl<P, S> #name for ::bench_vtables::sum_box::SumBox<::bench_vtables::typed::Typed<P, S>>
+        where
+            P: ::bench_vtables::typed::SieveLayout<Arg = #arg_type, Ret = #ret_type>,
+            S: ::bench_vtables::typed::TraitSet,
+        {
+            #(#shims)*
+        }
+    })
+}
+
+#[proc_macro_attribute]
+pub fn table_trait(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
+    let item = parse_macro_input!(item as ItemTrait);
+    parse_args(args)
+        .and_then(|args| expand(args, item))
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,307 @@
+//! The `repr(C)` layouts of the tables and pointers, and the C header describing them.
+//!
+//! Offsets are in words `w`, the size of a pointer. Functions are one word each.
+//!
+//! | Type | Field | Offset | Size |
+//! | -:|:-:|:-:|:-:|
+//! | `VTable<_, N>` | `funcs` | 0 | `N·w` |
+//! | `MultiVTable<_, T, F>` | `tables`, trait after trait | 0 | `T·F·w` |
+//! | `PackedSieveTable<_, T, F>` | `traits_ptr`, to each trait's `VTable<_, F>` | 0 | `T·w` |
+//! | | `trait_ids`, `u32`, `NO_TRAIT_ID` if unknown | `T·w` | `T·4` |
+//! | `InlineSieveTable<_, N>` | `funcs` | 0 | `N·w` |
+//! | | `default_sieve` | `N·w` | `w` |
+//! | `VPtr<_, _, N>` | `table` | 0 | `w` |
+//! | `MultiVPtr<_, _, T, F>` | `ptrs`, to each trait's `VTable<_, F>` | 0 | `T·w` |
+//! | `PackedSievePtr<_, _, T, F>` | `multi_v_mangled`, the table with the sieve in its low 8 bits | 0 | `w` |
+//! | `InlineSievePtr<_, _, N>` | `sieve` | 0 | `w` |
+//! | | `table`, whose `funcs` come first | `w` | `w` |
+//! | `HybridSievePtr<_, _, T, F>` | `primary`, the `VTable<_, F>` of the first visible trait | 0 | `w` |
+//! | | `mangled`, as `multi_v_mangled` | `w` | `w` |
+//! | `SplitSievePtr<_, _, T, F>` | `data_mangled`, the data with the sieve past `TABLE_BITS` | 0 | `w` |
+//! | | `table_mangled`, the table with the first `TABLE_BITS` bits | `w` | `w` |
+//! | `RelVPtr<_, _, N>` | `table` | 0 | `w` |
+//! | `RelPackedSievePtr<_, _, T, F>` | `multi_v_mangled` | 0 | `w` |
+//! | `TraitSearchPtr<_, _, T, F>` | `tables` | 0 | `w` |
+//! | | `indices`, `u8` | `w` | `T` |
+//! | `CppPtr<_, _, T, F>`, `CppBasePtr<_, _, T, F>` | `this` | 0 | `w` |
+//! | `ItabPtr<_, _, F>` | `cache` | 0 | `w` |
+//! | | `type_id`, `u32` | `w` | 4 |
+//! | `PerfectHashPtr<_, _, F, S>` | `table` | 0 | `w` |
+//! | `DisplacementPtr<_, _, F>` | `row` | 0 | `w` |
+//!
+//! The pointers from `HybridSievePtr` on are checked too, but their tables are not `repr(C)`: the
+//! header leaves them out.
+//!
+//! `PackedSieveTable` is aligned to 256 bytes. It and `InlineSieveTable` end with fields only Rust
+//! can read, so C only sees a prefix of them. The functions have the Rust ABI: C can copy and
+//! compare them, not call them.
+//!
+//! The offsets are checked at compile time below. [`c_header`] generates
+//! `include/bench_vtables.h` from them, run `cargo run --bin c_header` to update it.
+use std::mem::{align_of, offset_of, size_of};
+
+use crate::{
+    cpp_table::{CppBasePtr, CppPtr},
+    displacement::DisplacementPtr,
+    hybrid_sieve::HybridSievePtr,
+    inline_sieve_table::{InlineSievePtr, InlineSieveTable},
+    itab::ItabPtr,
+    multi_ptrs::{MultiVPtr, MultiVTable},
+    packed_sieve_table::{PackedSievePtr, PackedSieveTable},
+    perfect_hash::PerfectHashPtr,
+    rel_packed_sieve_table::RelPackedSievePtr,
+    rel_v_table::RelVPtr,
+    split_sieve::SplitSievePtr,
+    trait_search::TraitSearchPtr,
+    v_table::{VPtr, VTable},
+    TraitId, NO_TRAIT_ID,
+};
+
+const W: usize = size_of::<usize>();
+
+type V<const N: usize> = VPtr<'static, (), (), N>;
+type M<const T: usize, const F: usize> = MultiVPtr<'static, (), (), T, F>;
+type P<const T: usize, const F: usize> = PackedSievePtr<'static, (), (), T, F>;
+type I<const N: usize> = InlineSievePtr<'static, (), (), N>;
+
+const fn check_v_table<const N: usize>() -> bool {
+    offset_of!(VTable<V<N>, N>, funcs) == 0
+        && size_of::<VTable<V<N>, N>>() == N * W
+        && offset_of!(V<N>, table) == 0
+        && size_of::<V<N>>() == W
+}
+
+const fn check_multi_v_table<const T: usize, const F: usize>() -> bool {
+    offset_of!(MultiVTable<M<T, F>, T, F>, tables) == 0
+        && size_of::<MultiVTable<M<T, F>, T, F>>() == T * F * W
+        && offset_of!(M<T, F>, ptrs) == 0
+        && size_of::<M<T, F>>() == T * W
+}
+
+const fn check_packed_sieve_table<const T: usize, const F: usize>() -> bool {
+    offset_of!(PackedSieveTable<P<T, F>, T, F>, traits_ptr) == 0
+        && offset_of!(PackedSieveTable<P<T, F>, T, F>, trait_ids) == T * W
+        && align_of::<PackedSieveTable<P<T, F>, T, F>>() == 256
+        && offset_of!(P<T, F>, multi_v_mangled) == 0
+        && size_of::<P<T, F>>() == W
+}
+
+const fn check_inline_sieve_table<const N: usize>() -> bool {
+    offset_of!(InlineSieveTable<I<N>, N>, funcs) == 0
+        && offset_of!(InlineSieveTable<I<N>, N>, default_sieve) == N * W
+        && offset_of!(I<N>, sieve) == 0
+        && offset_of!(I<N>, table) == W
+        && size_of::<I<N>>() == 2 * W
+}
+
+/// The pointers whose tables C does not see.
+const fn check_other_ptrs<const T: usize, const F: usize>() -> bool {
+    type H<const T: usize, const F: usize> = HybridSievePtr<'static, (), (), T, F>;
+    type S<const T: usize, const F: usize> = SplitSievePtr<'static, (), (), T, F>;
+    type R<const F: usize> = RelVPtr<'static, (), (), F>;
+    type RP<const T: usize, const F: usize> = RelPackedSievePtr<'static, (), (), T, F>;
+    type TS<const T: usize, const F: usize> = TraitSearchPtr<'static, (), (), T, F>;
+    type C<const T: usize, const F: usize> = CppPtr<'static, (), (), T, F>;
+    type CB<const T: usize, const F: usize> = CppBasePtr<'static, (), (), T, F>;
+    type IT<const F: usize> = ItabPtr<'static, (), (), F>;
+    type PH<const F: usize> = PerfectHashPtr<'static, (), (), F, 16>;
+    type D<const F: usize> = DisplacementPtr<'static, (), (), F>;
+    offset_of!(H<T, F>, primary) == 0
+        && offset_of!(H<T, F>, mangled) == W
+        && size_of::<H<T, F>>() == 2 * W
+        && offset_of!(S<T, F>, data_mangled) == 0
+        && offset_of!(S<T, F>, table_mangled) == W
+        && size_of::<S<T, F>>() == 2 * W
+        && offset_of!(R<F>, table) == 0
+        && size_of::<R<F>>() == W
+        && offset_of!(RP<T, F>, multi_v_mangled) == 0
+        && size_of::<RP<T, F>>() == W
+        && offset_of!(TS<T, F>, tables) == 0
+        && offset_of!(TS<T, F>, indices) == W
+        && size_of::<TS<T, F>>() == (W + T).div_ceil(W) * W
+        && offset_of!(C<T, F>, this) == 0
+        && size_of::<C<T, F>>() == W
+        && offset_of!(CB<T, F>, this) == 0
+        && size_of::<CB<T, F>>() == W
+        && offset_of!(IT<F>, cache) == 0
+        && offset_of!(IT<F>, type_id) == W
+        && size_of::<IT<F>>() == 2 * W
+        && offset_of!(PH<F>, table) == 0
+        && size_of::<PH<F>>() == W
+        && offset_of!(D<F>, row) == 0
+        && size_of::<D<F>>() == W
+}
+
+const _: () = assert!(check_v_table::<1>() && check_v_table::<8>() && check_v_table::<16>());
+const _: () = assert!(check_multi_v_table::<1, 1>() && check_multi_v_table::<3, 3>());
+const _: () = assert!(check_packed_sieve_table::<1, 8>() && check_packed_sieve_table::<8, 1>());
+const _: () = assert!(check_inline_sieve_table::<1>() && check_inline_sieve_table::<64>());
+const _: () = assert!(check_other_ptrs::<1, 8>() && check_other_ptrs::<3, 3>());
+const _: () = assert!(check_other_ptrs::<8, 1>() && check_other_ptrs::<16, 1>());
+const _: () = assert!(size_of::<TraitId>() == 4 && NO_TRAIT_ID == u32::MAX);
+
+/// Returns `bytes` as a C expression in words, e.g. `3 * sizeof(void *)`.
+fn words(bytes: usize) -> String {
+    assert!(bytes % W == 0, "{} bytes is not a number of words", bytes);
+    match bytes / W {
+        0 => "0".to_string(),
+        1 => "sizeof(void *)".to_string(),
+        n => format!("{} * sizeof(void *)", n),
+    }
+}
+
+/// Appends a typedef of `c_type` and static assertions on its size, if C sees all of it, and on the
+/// offsets of its fields.
+fn typedef(
+    out: &mut String,
+    c_type: &str,
+    name: &str,
+    size: Option<usize>,
+    fields: &[(&str, usize)],
+) {
+    *out += &format!("\ntypedef {} {};\n", c_type, name);
+    if let Some(size) = size {
+        *out += &format!(
+            "_Static_assert(sizeof({}) == {}, \"{}\");\n",
+            name,
+            words(size),
+            name
+        );
+    }
+    for (field, offset) in fields {
+        *out += &format!(
+            "_Static_assert(offsetof({}, {}) == {}, \"{}.{}\");\n",
+            name,
+            field,
+            words(*offset),
+            name,
+            field
+        );
+    }
+}
+
+/// Returns the C header describing the tables and pointers.
+///
+/// The header declares a macro per type, generic over its parameters like the Rust type, and
+/// instantiates it for the configurations of `fibonacci` with static assertions on the offsets
+/// and sizes Rust computes.
+pub fn c_header() -> String {
+    let mut out = String::from(
+        "/* Generated by `cargo run --bin c_header`, do not edit. */\n\
+         #ifndef BENCH_VTABLES_H\n\
+         #define BENCH_VTABLES_H\n\
+         \n\
+         #include <stddef.h>\n\
+         #include <stdint.h>\n\
+         \n\
+         /* A function of a table. It has the Rust ABI: copy and compare it, don't call it. */\n\
+         typedef const void *bv_function;\n\
+         typedef uint32_t bv_trait_id;\n\
+         #define BV_NO_TRAIT_ID UINT32_MAX\n\
+         \n\
+         #define BV_VTABLE(N) struct { bv_function funcs[N]; }\n\
+         #define BV_MULTI_VTABLE(T, F) struct { bv_function tables[T][F]; }\n\
+         /* Followed by fields only Rust can read. */\n\
+         #define BV_PACKED_SIEVE_TABLE(T, F) \\\n    \
+         struct { _Alignas(256) const bv_function *traits_ptr[T]; bv_trait_id trait_ids[T]; }\n\
+         /* Followed by fields only Rust can read. */\n\
+         #define BV_INLINE_SIEVE_TABLE(N) struct { bv_function funcs[N]; uintptr_t default_sieve; }\n\
+         \n\
+         #define BV_VPTR(N) struct { const bv_function *table; }\n\
+         #define BV_MULTI_VPTR(T, F) struct { const bv_function *ptrs[T]; }\n\
+         /* The table, with the sieve in its low 8 bits. */\n\
+         #define BV_PACKED_SIEVE_PTR(T, F) struct { uintptr_t multi_v_mangled; }\n\
+         #define BV_PACKED_SIEVE_MASK ((uintptr_t)0xff)\n\
+         /* The table, whose funcs come first. */\n\
+         #define BV_INLINE_SIEVE_PTR(N) struct { uintptr_t sieve; const bv_function *table; }\n",
+    );
+
+    typedef(
+        &mut out,
+        "BV_VTABLE(8)",
+        "bv_vtable_8",
+        Some(size_of::<VTable<V<8>, 8>>()),
+        &[("funcs", offset_of!(VTable<V<8>, 8>, funcs))],
+    );
+    typedef(
+        &mut out,
+        "BV_MULTI_VTABLE(3, 3)",
+        "bv_multi_vtable_3_3",
+        Some(size_of::<MultiVTable<M<3, 3>, 3, 3>>()),
+        &[("tables", offset_of!(MultiVTable<M<3, 3>, 3, 3>, tables))],
+    );
+    typedef(
+        &mut out,
+        "BV_PACKED_SIEVE_TABLE(3, 3)",
+        "bv_packed_sieve_table_3_3",
+        None,
+        &[
+            (
+                "traits_ptr",
+                offset_of!(PackedSieveTable<P<3, 3>, 3, 3>, traits_ptr),
+            ),
+            (
+                "trait_ids",
+                offset_of!(PackedSieveTable<P<3, 3>, 3, 3>, trait_ids),
+            ),
+        ],
+    );
+    out += &format!(
+        "_Static_assert(_Alignof(bv_packed_sieve_table_3_3) == {}, \"bv_packed_sieve_table_3_3\");\n",
+        align_of::<PackedSieveTable<P<3, 3>, 3, 3>>()
+    );
+    typedef(
+        &mut out,
+        "BV_INLINE_SIEVE_TABLE(12)",
+        "bv_inline_sieve_table_12",
+        None,
+        &[
+            ("funcs", offset_of!(InlineSieveTable<I<12>, 12>, funcs)),
+            (
+                "default_sieve",
+                offset_of!(InlineSieveTable<I<12>, 12>, default_sieve),
+            ),
+        ],
+    );
+    typedef(
+        &mut out,
+        "BV_VPTR(8)",
+        "bv_vptr_8",
+        Some(size_of::<V<8>>()),
+        &[("table", offset_of!(V<8>, table))],
+    );
+    typedef(
+        &mut out,
+        "BV_MULTI_VPTR(3, 3)",
+        "bv_multi_vptr_3_3",
+        Some(size_of::<M<3, 3>>()),
+        &[("ptrs", offset_of!(M<3, 3>, ptrs))],
+    );
+    typedef(
+        &mut out,
+        "BV_PACKED_SIEVE_PTR(3, 3)",
+        "bv_packed_sieve_ptr_3_3",
+        Some(size_of::<P<3, 3>>()),
+        &[("multi_v_mangled", offset_of!(P<3, 3>, multi_v_mangled))],
+    );
+    typedef(
+        &mut out,
+        "BV_INLINE_SIEVE_PTR(12)",
+        "bv_inline_sieve_ptr_12",
+        Some(size_of::<I<12>>()),
+        &[
+            ("sieve", offset_of!(I<12>, sieve)),
+            ("table", offset_of!(I<12>, table)),
+        ],
+    );
+    out += "\n#endif /* BENCH_VTABLES_H */\n";
+    out
+}
+
+#[test]
+fn c_header_test() {
+    assert!(
+        c_header() == include_str!("../include/bench_vtables.h"),
+        "include/bench_vtables.h is out of date, run `cargo run --bin c_header > include/bench_vtables.h`"
+    );
+}
@@ -0,0 +1,6 @@
+//! Prints the C header describing the layouts of the tables and pointers, see `src/abi.rs`.
+//!
+//! Usage: `c_header > include/bench_vtables.h`
+fn main() {
+    print!("{}", bench_vtables::abi::c_header());
+}
@@ -0,0 +1,131 @@
+//! Prints the memory footprint of every fibonacci configuration benched by
+//! `cargo bench --bench fibonacci`, as a Markdown table.
+//!
+//! Usage: `footprint`
+use bench_vtables::{
+    cpp_table::CppPtr,
+    displacement::DisplacementPtr,
+    fibonacci::{
+        fibonacci, fibonacci_trait_ids, make_fibonacci_cpp, make_fibonacci_displacement,
+        make_fibonacci_inline_sieve_sized, make_fibonacci_itab_cache, make_fibonacci_multiptr,
+        make_fibonacci_packed_sieve, make_fibonacci_rel_packed_sieve,
+        make_fibonacci_rel_vtable_sized, make_fibonacci_trait_search, make_fibonacci_vtable_sized,
+    },
+    footprint::{Footprint, TableFootprint},
+    inline_sieve_table::InlineSievePtr,
+    itab::ItabPtr,
+    multi_ptrs::MultiVPtr,
+    multi_trait_matrix,
+    packed_sieve_table::PackedSievePtr,
+    rel_packed_sieve_table::RelPackedSievePtr,
+    rel_v_table::RelVPtr,
+    trait_search::TraitSearchPtr,
+    v_table::VPtr,
+};
+
+fn row(representation: &str, trait_n: usize, func_n: usize, footprint: Footprint) {
+    println!(
+        "| `{}` | {} | {} | {} | {} | {} | {} | {}/{} |",
+        representation,
+        trait_n,
+        func_n,
+        footprint.pointer_bytes,
+        footprint.table_bytes,
+        footprint.heap_bytes,
+        footprint.total_table_bytes(),
+        footprint.filler_slots,
+        footprint.slots,
+    );
+}
+
+fn vptr<const FUNC_N: usize>() {
+    let table = make_fibonacci_vtable_sized::<VPtr<u64, u64, FUNC_N>, FUNC_N>();
+    row("VPtr", 1, FUNC_N, table.footprint());
+}
+
+fn rel_vptr<const FUNC_N: usize>() {
+    let table = make_fibonacci_rel_vtable_sized::<RelVPtr<u64, u64, FUNC_N>, FUNC_N>();
+    row("RelVPtr", 1, FUNC_N, table.footprint());
+}
+
+fn multiptr<const TRAIT_N: usize, const FUNC_N: usize>() {
+    let table = make_fibonacci_multiptr::<MultiVPtr<u64, u64, TRAIT_N, FUNC_N>, TRAIT_N, FUNC_N>();
+    row("MultiVPtr", TRAIT_N, FUNC_N, table.footprint());
+}
+
+fn packed_sieve<const TRAIT_N: usize, const FUNC_N: usize>() {
+    let table =
+        make_fibonacci_packed_sieve::<PackedSievePtr<u64, u64, TRAIT_N, FUNC_N>, TRAIT_N, FUNC_N>();
+    row("PackedSievePtr", TRAIT_N, FUNC_N, table.footprint());
+}
+
+fn rel_packed_sieve<const TRAIT_N: usize, const FUNC_N: usize>() {
+    let table = make_fibonacci_rel_packed_sieve::<
+        RelPackedSievePtr<u64, u64, TRAIT_N, FUNC_N>,
+        TRAIT_N,
+        FUNC_N,
+    >();
+    row("RelPackedSievePtr", TRAIT_N, FUNC_N, table.footprint());
+}
+
+fn inline_sieve<const TRAIT_N: usize, const FUNC_N: usize, const N: usize>() {
+    let table =
+        make_fibonacci_inline_sieve_sized::<InlineSievePtr<u64, u64, N>, TRAIT_N, FUNC_N, N>();
+    row("InlineSievePtr", TRAIT_N, FUNC_N, table.footprint());
+}
+
+/// Reports the cache after a call, so that it has built the tables `fibonacci` needs.
+fn itab<const TRAIT_N: usize, const FUNC_N: usize>() {
+    let cache = make_fibonacci_itab_cache::<ItabPtr<u64, u64, FUNC_N>, TRAIT_N, FUNC_N>();
+    fibonacci(ItabPtr::new(&cache, 0), 5);
+    row("ItabPtr", TRAIT_N, FUNC_N, cache.footprint());
+}
+
+fn trait_search<const TRAIT_N: usize, const FUNC_N: usize>() {
+    let table =
+        make_fibonacci_trait_search::<TraitSearchPtr<u64, u64, TRAIT_N, FUNC_N>, TRAIT_N, FUNC_N>();
+    assert!(TraitSearchPtr::new(&table, fibonacci_trait_ids::<TRAIT_N>()).is_some());
+    row("TraitSearchPtr", TRAIT_N, FUNC_N, table.footprint());
+}
+
+/// The `TRAIT_N` table pointers in each object are not counted.
+fn cpp<const TRAIT_N: usize, const FUNC_N: usize>() {
+    let table = make_fibonacci_cpp::<CppPtr<u64, u64, TRAIT_N, FUNC_N>, TRAIT_N, FUNC_N>();
+    row("CppPtr", TRAIT_N, FUNC_N, table.footprint());
+}
+
+fn displacement<const TRAIT_N: usize, const FUNC_N: usize>() {
+    let table = make_fibonacci_displacement::<DisplacementPtr<u64, u64, FUNC_N>, TRAIT_N, FUNC_N>();
+    row("DisplacementPtr", TRAIT_N, FUNC_N, table.footprint());
+}
+
+/// Calls `$report::<TRAIT_N, FUNC_N>` for every `(TRAIT_N, FUNC_N)` pair.
+macro_rules! sweep {
+    ($report:ident, $(($trait_n:literal, $func_n:literal)),* $(,)?) => {
+        $($report::<$trait_n, $func_n>();)*
+    };
+}
+
+/// Like `sweep!`, also passing the number of slots of the inline sieve table.
+macro_rules! sweep_inline_sieve {
+    ($(($trait_n:literal, $func_n:literal)),* $(,)?) => {
+        $(inline_sieve::<$trait_n, $func_n, { $trait_n * ($func_n + 1) }>();)*
+    };
+}
+
+fn main() {
+    println!("| | Traits | Methods | Pointer | Table | Heap | Total | Fillers |");
+    println!("| -:|:-:|:-:|-:|-:|-:|-:|:-:|");
+    vptr::<8>();
+    vptr::<16>();
+    rel_vptr::<8>();
+    rel_vptr::<16>();
+    multi_trait_matrix!(sweep!(packed_sieve,));
+    multi_trait_matrix!(sweep!(rel_packed_sieve,));
+    multi_trait_matrix!(sweep!(multiptr,), beyond_8);
+    multi_trait_matrix!(sweep_inline_sieve!(), inline_sieve);
+    multi_trait_matrix!(sweep!(itab,), beyond_8);
+    multi_trait_matrix!(sweep!(trait_search,), beyond_8);
+    multi_trait_matrix!(sweep!(cpp,), beyond_8);
+    multi_trait_matrix!(sweep!(displacement,), beyond_8);
+}
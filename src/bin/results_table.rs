@@ -0,0 +1,394 @@
+//! Builds the README results table from the output of `cargo bench --bench fibonacci`.
+//!
+//! Reads `<criterion dir>/Fibonacci/<function id>/<n>/new/estimates.json`. By default, prints the
+//! README table from the benchmarks with the IDs of its 2021 run, e.g. `PackedSievePtr_3trait`.
+//! `--matrix` prints the benchmarks of `FIBONACCI_SWEEP=1` instead, with IDs such as
+//! `PackedSievePtr_T3_F4`, a row per representation and a column per number of traits. When a
+//! representation was benched with several numbers of methods per trait, the table shows the
+//! smallest one (the densest layout). `--csv` and `--json` export all of them.
+//!
+//! Usage: `results_table [--criterion-dir DIR] [--n N] [--markdown | --matrix | --csv | --json]`
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fs,
+    mem::size_of,
+    path::{Path, PathBuf},
+    process,
+};
+
+use bench_vtables::{
+    cpp_table::CppPtr, displacement::DisplacementPtr, inline_sieve_table::InlineSievePtr,
+    itab::ItabPtr, multi_ptrs::MultiVPtr, packed_sieve_table::PackedSievePtr,
+    rel_packed_sieve_table::RelPackedSievePtr, rel_v_table::RelVPtr, v_table::VPtr,
+};
+
+/// The representations, in the order of the table.
+const REPRESENTATIONS: [&str; 10] = [
+    "VPtr",
+    "RelVPtr",
+    "PackedSievePtr",
+    "RelPackedSievePtr",
+    "InlineSievePtr",
+    "MultiVPtr",
+    "ItabPtr",
+    "TraitSearchPtr",
+    "CppPtr",
+    "DisplacementPtr",
+];
+
+/// The representations of the README table, in its order.
+const README_REPRESENTATIONS: [&str; 4] = ["VPtr", "PackedSievePtr", "InlineSievePtr", "MultiVPtr"];
+
+/// The mean time of a benchmark.
+struct Estimate {
+    function_id: String,
+    n: u64,
+    mean_ns: f64,
+}
+
+/// One benchmark result of the matrix.
+#[derive(Debug, PartialEq)]
+struct Measure {
+    representation: String,
+    trait_n: usize,
+    func_n: usize,
+    n: u64,
+    mean_ns: f64,
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Markdown,
+    Matrix,
+    Csv,
+    Json,
+}
+
+/// Returns the function ID of the README table for the representation with `trait_n` traits.
+///
+/// The inline sieve was benched once, with 12 slots, and shows for every number of traits.
+fn readme_function_id(representation: &str, trait_n: usize) -> Option<String> {
+    match (representation, trait_n) {
+        ("VPtr", 1) => Some("VPtr".to_string()),
+        ("InlineSievePtr", 2..=5) => Some("InlineSievePtr".to_string()),
+        ("PackedSievePtr" | "MultiVPtr", 2..=5) => {
+            Some(format!("{}_{}trait", representation, trait_n))
+        }
+        _ => None,
+    }
+}
+
+/// Parses a function ID such as `PackedSievePtr_T3_F4`.
+fn parse_function_id(id: &str) -> Option<(&str, usize, usize)> {
+    let mut parts = id.rsplitn(3, '_');
+    let func_n = parts.next()?.strip_prefix('F')?.parse().ok()?;
+    let trait_n = parts.next()?.strip_prefix('T')?.parse().ok()?;
+    Some((parts.next()?, trait_n, func_n))
+}
+
+/// Returns the size of a `Box` of the representation with `trait_n` traits, in words.
+///
+/// Counts the data pointer, which the pointers of this crate don't have.
+fn box_words(representation: &str, trait_n: usize) -> Option<usize> {
+    let word = size_of::<usize>();
+    let meta = match representation {
+        "VPtr" => size_of::<VPtr<(), (), 1>>(),
+        "RelVPtr" => size_of::<RelVPtr<(), (), 1>>(),
+        "PackedSievePtr" => size_of::<PackedSievePtr<(), (), 1, 1>>(),
+        "RelPackedSievePtr" => size_of::<RelPackedSievePtr<(), (), 1, 1>>(),
+        "InlineSievePtr" => size_of::<InlineSievePtr<(), (), 1>>(),
+        "ItabPtr" => size_of::<ItabPtr<(), (), 1>>(),
+        "MultiVPtr" => size_of::<MultiVPtr<(), (), 1, 1>>() * trait_n,
+        "DisplacementPtr" => size_of::<DisplacementPtr<(), (), 1>>(),
+        // The pointer is the data pointer, the table pointers are in the object.
+        "CppPtr" => size_of::<CppPtr<(), (), 1, 1>>() - word,
+        // A table pointer and a byte per trait.
+        "TraitSearchPtr" => (size_of::<usize>() + trait_n).div_ceil(word) * word,
+        _ => return None,
+    };
+    Some(meta / word + 1)
+}
+
+/// Returns e.g. `2 words` or `N+1 words` if the size depends on the number of traits `N`.
+fn size_label(representation: &str) -> String {
+    match (box_words(representation, 1), box_words(representation, 2)) {
+        (Some(1), Some(1)) => "1 word".to_string(),
+        (Some(one), Some(two)) if one == two => format!("{} words", one),
+        (Some(one), Some(two)) if two - one == 1 => format!("N+{} words", 2 * one - two),
+        (Some(one), Some(two)) => format!("{}N+{} words", two - one, 2 * one - two),
+        _ => "?".to_string(),
+    }
+}
+
+fn read_estimates(criterion_dir: &Path) -> Result<Vec<Estimate>, String> {
+    let group_dir = criterion_dir.join("Fibonacci");
+    let functions =
+        fs::read_dir(&group_dir).map_err(|e| format!("{}: {}", group_dir.display(), e))?;
+    let mut estimates = Vec::new();
+    for function in functions {
+        let function = function.map_err(|e| e.to_string())?;
+        let function_id = function.file_name().to_string_lossy().into_owned();
+        // Criterion's `report` directory.
+        if !function.path().is_dir() || function_id == "report" {
+            continue;
+        }
+        for value in fs::read_dir(function.path()).map_err(|e| e.to_string())? {
+            let value = value.map_err(|e| e.to_string())?;
+            let n = match value.file_name().to_string_lossy().parse() {
+                Ok(n) => n,
+                Err(_) => continue,
+            };
+            let path = value.path().join("new").join("estimates.json");
+            let text =
+                fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let json: serde_json::Value =
+                serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let mean_ns = json["mean"]["point_estimate"]
+                .as_f64()
+                .ok_or_else(|| format!("{}: no mean.point_estimate", path.display()))?;
+            estimates.push(Estimate {
+                function_id: function_id.clone(),
+                n,
+                mean_ns,
+            });
+        }
+    }
+    Ok(estimates)
+}
+
+/// Returns the estimates of the matrix, sorted.
+fn measures(estimates: &[Estimate]) -> Vec<Measure> {
+    let mut measures: Vec<Measure> = estimates
+        .iter()
+        .filter_map(|e| {
+            let (representation, trait_n, func_n) = parse_function_id(&e.function_id)?;
+            Some(Measure {
+                representation: representation.to_string(),
+                trait_n,
+                func_n,
+                n: e.n,
+                mean_ns: e.mean_ns,
+            })
+        })
+        .collect();
+    measures.sort_by(|a, b| {
+        (&a.representation, a.trait_n, a.func_n, a.n).cmp(&(
+            &b.representation,
+            b.trait_n,
+            b.func_n,
+            b.n,
+        ))
+    });
+    measures
+}
+
+/// Returns the README table for `fibonacci(n)`.
+fn readme_markdown(estimates: &[Estimate], n: u64) -> String {
+    let mean = |function_id: &str| {
+        estimates
+            .iter()
+            .find(|e| e.n == n && e.function_id == function_id)
+            .map(|e| e.mean_ns)
+    };
+    let mut out = String::from("| | Size |");
+    let mut rule = String::from("| -:|:-:|");
+    for t in 1..=5 {
+        out += &format!(" {} trait{} |", t, if t == 1 { "" } else { "s" });
+        rule += ":-:|";
+    }
+    out += "\n";
+    out += &rule;
+    out += "\n";
+    for representation in README_REPRESENTATIONS {
+        out += &format!("| `{}` | {} |", representation, size_label(representation));
+        for t in 1..=5 {
+            match readme_function_id(representation, t).and_then(|id| mean(&id)) {
+                Some(mean_ns) => out += &format!(" {:.0} ns |", mean_ns),
+                None => out += " - |",
+            }
+        }
+        out += "\n";
+    }
+    out
+}
+
+/// Returns the Markdown table of the matrix for `fibonacci(n)`.
+fn matrix_markdown(measures: &[Measure], n: u64) -> String {
+    // For each (representation, trait_n), the measure with the fewest methods per trait.
+    let mut cells: BTreeMap<(&str, usize), &Measure> = BTreeMap::new();
+    for m in measures.iter().filter(|m| m.n == n) {
+        let cell = cells.entry((&m.representation, m.trait_n)).or_insert(m);
+        if m.func_n < cell.func_n {
+            *cell = m;
+        }
+    }
+    let trait_ns: BTreeSet<usize> = cells.keys().map(|(_, t)| *t).collect();
+    let mut representations: Vec<&str> = REPRESENTATIONS.to_vec();
+    for (representation, _) in cells.keys() {
+        if !representations.contains(representation) {
+            representations.push(representation);
+        }
+    }
+
+    let mut out = String::from("| | Size |");
+    let mut rule = String::from("| -:|:-:|");
+    for t in &trait_ns {
+        out += &format!(" {} trait{} |", t, if *t == 1 { "" } else { "s" });
+        rule += ":-:|";
+    }
+    out += "\n";
+    out += &rule;
+    out += "\n";
+    for representation in representations {
+        if !cells.keys().any(|(r, _)| *r == representation) {
+            continue;
+        }
+        out += &format!("| `{}` | {} |", representation, size_label(representation));
+        for t in &trait_ns {
+            match cells.get(&(representation, *t)) {
+                Some(m) => out += &format!(" {:.0} ns |", m.mean_ns),
+                None => out += " - |",
+            }
+        }
+        out += "\n";
+    }
+    out
+}
+
+fn csv(measures: &[Measure]) -> String {
+    let mut out = String::from("representation,trait_n,func_n,n,words,mean_ns\n");
+    for m in measures {
+        let words = box_words(&m.representation, m.trait_n)
+            .map(|w| w.to_string())
+            .unwrap_or_default();
+        out += &format!(
+            "{},{},{},{},{},{}\n",
+            m.representation, m.trait_n, m.func_n, m.n, words, m.mean_ns
+        );
+    }
+    out
+}
+
+fn json(measures: &[Measure]) -> String {
+    let array: Vec<serde_json::Value> = measures
+        .iter()
+        .map(|m| {
+            serde_json::json!({
+                "representation": m.representation,
+                "trait_n": m.trait_n,
+                "func_n": m.func_n,
+                "n": m.n,
+                "words": box_words(&m.representation, m.trait_n),
+                "mean_ns": m.mean_ns,
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&array).expect("serializing JSON values never fails") + "\n"
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: results_table [--criterion-dir DIR] [--n N] [--markdown | --matrix | --csv | --json]"
+    );
+    process::exit(2);
+}
+
+fn main() {
+    let target_dir = env::var_os("CARGO_TARGET_DIR").unwrap_or_else(|| "target".into());
+    let mut criterion_dir = PathBuf::from(target_dir).join("criterion");
+    let mut n = 10;
+    let mut format = Format::Markdown;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--criterion-dir" => criterion_dir = args.next().unwrap_or_else(|| usage()).into(),
+            "--n" => {
+                n = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or_else(|| usage())
+            }
+            "--markdown" => format = Format::Markdown,
+            "--matrix" => format = Format::Matrix,
+            "--csv" => format = Format::Csv,
+            "--json" => format = Format::Json,
+            _ => usage(),
+        }
+    }
+
+    let estimates = read_estimates(&criterion_dir).unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        eprintln!("hint: run `cargo bench --bench fibonacci` first");
+        process::exit(1);
+    });
+    let measures = measures(&estimates);
+    let out = match format {
+        Format::Markdown => readme_markdown(&estimates, n),
+        Format::Matrix => matrix_markdown(&measures, n),
+        Format::Csv => csv(&measures),
+        Format::Json => json(&measures),
+    };
+    print!("{}", out);
+}
+
+#[test]
+fn parse_function_id_test() {
+    assert!(parse_function_id("PackedSievePtr_T3_F4") == Some(("PackedSievePtr", 3, 4)));
+    assert!(parse_function_id("VPtr_T1_F16") == Some(("VPtr", 1, 16)));
+    assert!(parse_function_id("PackedSievePtr_3trait").is_none());
+    assert!(parse_function_id("report").is_none());
+}
+
+#[test]
+fn matrix_markdown_test() {
+    let measure = |representation: &str, trait_n, func_n, mean_ns| Measure {
+        representation: representation.to_string(),
+        trait_n,
+        func_n,
+        n: 10,
+        mean_ns,
+    };
+    let measures = [
+        measure("MultiVPtr", 2, 4, 472.4),
+        measure("PackedSievePtr", 2, 8, 500.0),
+        measure("PackedSievePtr", 2, 4, 464.0),
+        measure("PackedSievePtr", 3, 3, 488.0),
+        measure("VPtr", 1, 8, 402.0),
+    ];
+    assert!(size_label("VPtr") == "2 words");
+    assert!(size_label("MultiVPtr") == "N+1 words");
+    assert!(
+        matrix_markdown(&measures, 10)
+            == "| | Size | 1 trait | 2 traits | 3 traits |\n\
+                | -:|:-:|:-:|:-:|:-:|\n\
+                | `VPtr` | 2 words | 402 ns | - | - |\n\
+                | `PackedSievePtr` | 2 words | - | 464 ns | 488 ns |\n\
+                | `MultiVPtr` | N+1 words | - | 472 ns | - |\n"
+    );
+}
+
+#[test]
+fn readme_markdown_test() {
+    let estimate = |function_id: &str, mean_ns| Estimate {
+        function_id: function_id.to_string(),
+        n: 10,
+        mean_ns,
+    };
+    let estimates = [
+        estimate("VPtr", 402.0),
+        estimate("PackedSievePtr_2trait", 464.0),
+        estimate("InlineSievePtr", 510.0),
+        estimate("MultiVPtr_3trait", 753.0),
+        // From the matrix, not in the README table.
+        estimate("PackedSievePtr_T3_F3", 488.0),
+    ];
+    assert!(
+        readme_markdown(&estimates, 10)
+            == "| | Size | 1 trait | 2 traits | 3 traits | 4 traits | 5 traits |\n\
+                | -:|:-:|:-:|:-:|:-:|:-:|:-:|\n\
+                | `VPtr` | 2 words | 402 ns | - | - | - | - |\n\
+                | `PackedSievePtr` | 2 words | - | 464 ns | - | - | - |\n\
+                | `InlineSievePtr` | 3 words | - | 510 ns | 510 ns | 510 ns | 510 ns |\n\
+                | `MultiVPtr` | N+1 words | - | - | 753 ns | - | - |\n"
+    );
+}
@@ -0,0 +1,237 @@
+//! Iterates over a `Vec` of objects of mixed concrete types behind the same sum trait.
+//!
+//! Unlike `fibonacci`, every object has its own table depending on its concrete type (its "kind"),
+//! and the objects are shuffled so that the branch predictor cannot guess the target.
+//!
+//! There are `TRAIT_N` traits with `FUNC_N` methods each. The loop calls one method of each trait,
+//! which reads a field of the object: every call loads the table and the boxed value.
+use crate::{
+    inline_sieve_table::InlineSieveTable, multi_ptrs::MultiVTable,
+    packed_sieve_table::PackedSieveTable, sum_box::SumBox, v_table::VTable, FuncTablePtr, Function,
+};
+
+/// Number of concrete types.
+pub const KIND_N: usize = 4;
+/// Number of traits each concrete type implements.
+pub const TRAIT_N: usize = 3;
+/// Number of methods per trait.
+pub const FUNC_N: usize = 2;
+
+/// The concrete type of the kind `LEN - 1`: the kinds have different sizes.
+#[repr(C)]
+pub struct Object<const LEN: usize> {
+    fields: [u64; LEN],
+}
+
+mod funcptrs {
+    use super::Object;
+    use crate::{FuncTablePtr, Function};
+
+    /// The `M`-th method of the concrete type `K`, an `Object<LEN>`.
+    ///
+    /// Each trait reads another field of the object, if it has enough of them.
+    pub fn method<const K: u64, const LEN: usize, const M: u64>(data: *const (), arg: u64) -> u64 {
+        // The tables of the kind `K` are only paired with an `Object<LEN>`.
+        let object = unsafe { &*(data as *const Object<LEN>) };
+        let field = object.fields[(M as usize / super::FUNC_N) % LEN];
+        super::expected_method(K as usize, M as u32, field, arg)
+    }
+
+    fn kind_funcs<I, const K: u64, const LEN: usize>(
+    ) -> [Function<I>; super::TRAIT_N * super::FUNC_N]
+    where
+        I: FuncTablePtr<Arg = u64, Ret = u64>,
+    {
+        [
+            method::<K, LEN, 0>,
+            method::<K, LEN, 1>,
+            method::<K, LEN, 2>,
+            method::<K, LEN, 3>,
+            method::<K, LEN, 4>,
+            method::<K, LEN, 5>,
+        ]
+    }
+
+    /// Returns the methods of the concrete type `kind`, trait after trait.
+    pub fn funcs<I>(kind: usize) -> [Function<I>; super::TRAIT_N * super::FUNC_N]
+    where
+        I: FuncTablePtr<Arg = u64, Ret = u64>,
+    {
+        match kind {
+            0 => kind_funcs::<I, 0, 1>(),
+            1 => kind_funcs::<I, 1, 2>(),
+            2 => kind_funcs::<I, 2, 3>(),
+            3 => kind_funcs::<I, 3, 4>(),
+            _ => panic!("kind {} is not below {}", kind, super::KIND_N),
+        }
+    }
+}
+
+/// What the `method`-th method of the concrete type `kind` returns, for an object whose fields
+/// are all `value`.
+#[inline(always)]
+pub fn expected_method(kind: usize, method: u32, value: u64, arg: u64) -> u64 {
+    arg.wrapping_mul(kind as u64 + 1)
+        .wrapping_add(method as u64)
+        .wrapping_add(value)
+}
+
+/// Returns `count` kinds, each kind appearing the same number of times, in a random order.
+///
+/// The order only depends on `seed`.
+pub fn shuffled_kinds(count: usize, seed: u64) -> Vec<usize> {
+    let mut kinds: Vec<usize> = (0..count).map(|i| i % KIND_N).collect();
+    crate::random::shuffle(&mut kinds, seed);
+    kinds
+}
+
+/// Boxes an object of each of `kinds`, the `i`-th with all its fields set to `i`, and pairs it
+/// with the pointer of its kind in `metas`.
+///
+/// # Safety
+///
+/// Behavior is undefined unless `metas[kind]` points to the table `make_collection_*` returns for
+/// `kind`, for every kind of `kinds`.
+pub unsafe fn make_objects<I>(kinds: &[usize], metas: &[I]) -> Vec<SumBox<I>>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64, Markers = ()>,
+{
+    kinds
+        .iter()
+        .enumerate()
+        .map(|(i, kind)| {
+            let (value, meta) = (i as u64, metas[*kind]);
+            match kind {
+                0 => SumBox::new(Object { fields: [value; 1] }, meta),
+                1 => SumBox::new(Object { fields: [value; 2] }, meta),
+                2 => SumBox::new(Object { fields: [value; 3] }, meta),
+                3 => SumBox::new(Object { fields: [value; 4] }, meta),
+                _ => panic!("kind {} is not below {}", kind, KIND_N),
+            }
+        })
+        .collect()
+}
+
+/// Calls the first method of every trait on every object, and returns the sum of the results.
+///
+/// # Safety
+///
+/// Behavior is undefined unless the objects come from `make_objects`.
+pub unsafe fn sum_methods<I>(objects: &[SumBox<I>], arg: u64) -> u64
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64, Markers = ()>,
+{
+    let mut sum = 0u64;
+    for object in objects {
+        for trait_n in 0..TRAIT_N as u32 {
+            sum = sum.wrapping_add(object.call(trait_n * FUNC_N as u32, arg));
+        }
+    }
+    sum
+}
+
+/// A single trait holding the methods of all traits.
+pub fn make_collection_vtable<I>(kind: usize) -> VTable<I, { TRAIT_N * FUNC_N }>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    VTable::new(funcptrs::funcs::<I>(kind))
+}
+
+pub fn make_collection_multiptr<I>(kind: usize) -> MultiVTable<I, TRAIT_N, FUNC_N>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    MultiVTable::new(&funcptrs::funcs::<I>(kind))
+}
+
+pub fn make_collection_packed_sieve<I>(kind: usize) -> PackedSieveTable<I, TRAIT_N, FUNC_N>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    PackedSieveTable::new(&funcptrs::funcs::<I>(kind))
+}
+
+/// Interleaves the methods with holes, as if the table had traits the pointer cannot see.
+pub fn make_collection_inline_sieve<I>(kind: usize) -> InlineSieveTable<I, 8>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    let [f0, f1, f2, f3, f4, f5]: [Function<I>; 6] = funcptrs::funcs::<I>(kind);
+    InlineSieveTable::new([
+        Some(f0),
+        Some(f1),
+        None,
+        Some(f2),
+        Some(f3),
+        None,
+        Some(f4),
+        Some(f5),
+    ])
+}
+
+#[cfg(test)]
+fn sum_methods_expected(kinds: &[usize], arg: u64) -> u64 {
+    let mut sum = 0u64;
+    for (i, kind) in kinds.iter().enumerate() {
+        for trait_n in 0..TRAIT_N as u32 {
+            let method = trait_n * FUNC_N as u32;
+            sum = sum.wrapping_add(expected_method(*kind, method, i as u64, arg));
+        }
+    }
+    sum
+}
+
+#[test]
+fn shuffled_kinds_test() {
+    let kinds = shuffled_kinds(100, 42);
+    assert!(kinds == shuffled_kinds(100, 42));
+    assert!(kinds != shuffled_kinds(100, 43));
+    for kind in 0..KIND_N {
+        assert!(kinds.iter().filter(|k| **k == kind).count() == 25);
+    }
+}
+
+#[test]
+fn collection_vptr_test() {
+    use crate::v_table::VPtr;
+
+    let kinds = shuffled_kinds(100, 42);
+    let tables: Vec<_> = (0..KIND_N).map(make_collection_vtable).collect();
+    let metas: Vec<_> = tables.iter().map(VPtr::new).collect();
+    let objects = unsafe { make_objects(&kinds, &metas) };
+    assert!(unsafe { sum_methods(&objects, 3) } == sum_methods_expected(&kinds, 3));
+}
+
+#[test]
+fn collection_multiptr_test() {
+    use crate::multi_ptrs::MultiVPtr;
+
+    let kinds = shuffled_kinds(100, 42);
+    let tables: Vec<_> = (0..KIND_N).map(make_collection_multiptr).collect();
+    let metas: Vec<_> = tables.iter().map(MultiVPtr::new).collect();
+    let objects = unsafe { make_objects(&kinds, &metas) };
+    assert!(unsafe { sum_methods(&objects, 3) } == sum_methods_expected(&kinds, 3));
+}
+
+#[test]
+fn collection_packed_sieve_test() {
+    use crate::packed_sieve_table::PackedSievePtr;
+
+    let kinds = shuffled_kinds(100, 42);
+    let tables: Vec<_> = (0..KIND_N).map(make_collection_packed_sieve).collect();
+    let metas: Vec<_> = tables.iter().map(PackedSievePtr::new).collect();
+    let objects = unsafe { make_objects(&kinds, &metas) };
+    assert!(unsafe { sum_methods(&objects, 3) } == sum_methods_expected(&kinds, 3));
+}
+
+#[test]
+fn collection_inline_sieve_test() {
+    use crate::inline_sieve_table::InlineSievePtr;
+
+    let kinds = shuffled_kinds(100, 42);
+    let tables: Vec<_> = (0..KIND_N).map(make_collection_inline_sieve).collect();
+    let metas: Vec<_> = tables.iter().map(InlineSievePtr::new).collect();
+    let objects = unsafe { make_objects(&kinds, &metas) };
+    assert!(unsafe { sum_methods(&objects, 3) } == sum_methods_expected(&kinds, 3));
+}
@@ -0,0 +1,165 @@
+//! Conversions between the representations, for experiments.
+//!
+//! The converted pointer points to the tables of the original one: the conversions allocate
+//! nothing, and the pointers see the same functions, in the same order. The functions that pair
+//! their data with a pointer again, like those of `fibonacci`, expect the representation they were
+//! built for: call them through the original pointer.
+use crate::{
+    inline_sieve_table::InlineSievePtr,
+    multi_ptrs::MultiVPtr,
+    packed_sieve_table::PackedSievePtr,
+    v_table::{VPtr, VTable},
+    FuncTablePtr,
+};
+
+/// Views a table as the table of another pointer type with the same functions.
+fn cast_v_table<P, Q, const N: usize>(table: &VTable<P, N>) -> &VTable<Q, N>
+where
+    P: FuncTablePtr,
+    Q: FuncTablePtr<Arg = P::Arg, Ret = P::Ret>,
+{
+    // The function types only depend on `Arg` and `Ret`.
+    unsafe { &*(table as *const VTable<P, N> as *const VTable<Q, N>) }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize>
+    PackedSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    /// Returns an extra-fat pointer with a table per visible trait, or `None` unless `K` traits
+    /// are visible.
+    pub fn to_multi_v_ptr<const K: usize>(self) -> Option<MultiVPtr<'vt, ArgT, RetT, K, FUNC_N>> {
+        let sieve = self.sieve();
+        if sieve.count_ones() as usize != K || K == 0 {
+            return None;
+        }
+        // The pointer was built from a reference to the table.
+        let table = unsafe { self.multi_v() };
+        let mut traits = (0..TRAIT_N)
+            .filter(|t| sieve & 1 << t != 0)
+            .map(|t| cast_v_table(unsafe { &*table.traits_ptr[t] }));
+        let first = traits.next()?;
+        let mut ptrs = [first; K];
+        for (ptr, table) in ptrs[1..].iter_mut().zip(traits) {
+            *ptr = table;
+        }
+        Some(MultiVPtr { ptrs })
+    }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize>
+    MultiVPtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    /// Returns a pointer to the `trait_n`-th trait, or `None` if there is no such trait.
+    pub fn to_v_ptr(self, trait_n: usize) -> Option<VPtr<'vt, ArgT, RetT, FUNC_N>> {
+        let table = self.ptrs.get(trait_n)?;
+        Some(VPtr::new(cast_v_table(table)))
+    }
+}
+
+impl<'vt, ArgT, RetT, const N: usize> InlineSievePtr<'vt, ArgT, RetT, N> {
+    /// Returns a pointer to the `FUNC_N` visible functions, or `None` unless exactly `FUNC_N`
+    /// consecutive slots are visible, as when the pointer sees one trait without hidden slots.
+    pub fn to_v_ptr<const FUNC_N: usize>(self) -> Option<VPtr<'vt, ArgT, RetT, FUNC_N>> {
+        let first = self.sieve.trailing_zeros() as usize;
+        let ones = self.sieve.checked_shr(first as u32).unwrap_or(0);
+        if FUNC_N == 0 || FUNC_N > N || ones != usize::MAX >> (usize::BITS as usize - FUNC_N) {
+            return None;
+        }
+        // The slots from `first` are in the array, and a `VTable` is an array of functions.
+        let funcs = &self.table.funcs[first..first + FUNC_N];
+        let table =
+            unsafe { &*(funcs.as_ptr() as *const VTable<VPtr<'vt, ArgT, RetT, FUNC_N>, FUNC_N>) };
+        Some(VPtr::new(table))
+    }
+}
+
+#[cfg(test)]
+fn add<const K: u64>(data: *const (), x: u64) -> u64 {
+    x + K + unsafe { *(data as *const u64) }
+}
+
+/// Checks that both pointers call the same functions for the first `func_n` indices.
+#[cfg(test)]
+fn assert_same_dispatch<P, Q>(before: P, after: Q, func_n: u32)
+where
+    P: FuncTablePtr<Arg = u64, Ret = u64>,
+    Q: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    let data: *const () = &1000u64 as *const u64 as *const ();
+    for n in 0..func_n {
+        let (before, after) = unsafe {
+            (
+                before.get_function(n)(data, 1),
+                after.get_function(n)(data, 1),
+            )
+        };
+        assert!(
+            before == after,
+            "function {}: {} before, {} after",
+            n,
+            before,
+            after
+        );
+    }
+}
+
+#[test]
+fn packed_sieve_to_multi_v_ptr_test() {
+    use crate::{packed_sieve_table::PackedSieveTable, ProjectPtr};
+
+    let table = PackedSieveTable::<_, 3, 2>::new(&[
+        add::<0>, add::<1>, add::<2>, add::<3>, add::<4>, add::<5>,
+    ]);
+    let packed = PackedSievePtr::new(&table);
+    let multi = packed.to_multi_v_ptr::<3>().unwrap();
+    assert_same_dispatch(packed, multi, 6);
+    assert!(packed.to_multi_v_ptr::<2>().is_none());
+
+    let packed = unsafe { packed.project(0b101) };
+    let multi = packed.to_multi_v_ptr::<2>().unwrap();
+    assert_same_dispatch(packed, multi, 4);
+    assert!(unsafe { multi.get_function(2)(&0u64 as *const u64 as *const (), 0) } == 4);
+
+    let single = multi.to_v_ptr(1).unwrap();
+    assert_same_dispatch(unsafe { packed.project(0b10) }, single, 2);
+    assert!(multi.to_v_ptr(2).is_none());
+}
+
+#[test]
+fn packed_sieve_padded_to_multi_v_ptr_test() {
+    use crate::packed_sieve_table::PackedSieveTable;
+
+    // 4 functions for 3 traits of 2: the 3rd trait is all fillers, but has a table.
+    let table = PackedSieveTable::<_, 3, 2>::new(&[add::<0>, add::<1>, add::<2>, add::<3>]);
+    let packed = PackedSievePtr::new(&table);
+    let multi = packed.to_multi_v_ptr::<3>().unwrap();
+    assert_same_dispatch(packed, multi, 4);
+    let result = std::panic::catch_unwind(|| unsafe { multi.get_function(4)(std::ptr::null(), 0) });
+    assert!(result.is_err(), "the filler should panic");
+}
+
+#[test]
+fn inline_sieve_to_v_ptr_test() {
+    use crate::{inline_sieve_table::InlineSieveTable, ProjectPtr};
+
+    // Two traits of two methods, each followed by a hidden slot.
+    let table = InlineSieveTable::new([
+        Some(add::<0>),
+        Some(add::<1>),
+        None,
+        Some(add::<2>),
+        Some(add::<3>),
+        None,
+    ]);
+    let inline = InlineSievePtr::new(&table);
+    assert!(inline.to_v_ptr::<2>().is_none());
+    assert!(inline.to_v_ptr::<4>().is_none());
+
+    let second = unsafe { inline.project(0b1100) };
+    let single = second.to_v_ptr::<2>().unwrap();
+    assert_same_dispatch(second, single, 2);
+    assert!(second.to_v_ptr::<1>().is_none());
+
+    // The first method of each trait: not consecutive.
+    assert!(unsafe { inline.project(0b0101) }.to_v_ptr::<2>().is_none());
+}
@@ -0,0 +1,199 @@
+//! The C++ layout of multiple inheritance, with the traits as base classes.
+//!
+//! The table is the concatenation of a sub-table per base, each starting with the offset from
+//! the base's sub-object to the complete object ("offset to top" in the Itanium ABI). Unlike the
+//! other representations, the table pointers are in the object: `CppObject` holds a `vptr` per
+//! base, and the pointer is a thin pointer to it.
+//!
+//! Calling a method of base `t` through the complete object reads the `t`-th `vptr`, at a
+//! static offset. Upcasting to base `t` moves the pointer to that `vptr`. The methods receive the
+//! data pointer of the object rather than `this`, so calling one through a base needs no thunk;
+//! `CppBasePtr::top` moves the pointer back to the complete object by the offset to top.
+use std::{marker::PhantomData, mem::size_of};
+
+use crate::{
+    filler_function,
+    footprint::{count_fillers, Footprint, TableFootprint},
+    FuncTablePtr, Function,
+};
+
+/// The part of the table for one base.
+#[repr(C)]
+pub struct SubTable<Ptr: FuncTablePtr, const FUNC_N: usize> {
+    /// In bytes, from the `vptr` pointing to this sub-table to the first `vptr` of the object.
+    offset_to_top: isize,
+    funcs: [Function<Ptr>; FUNC_N],
+}
+
+impl<Ptr: FuncTablePtr, const FUNC_N: usize> Copy for SubTable<Ptr, FUNC_N> {}
+impl<Ptr: FuncTablePtr, const FUNC_N: usize> Clone for SubTable<Ptr, FUNC_N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+#[repr(C)]
+pub struct CppTable<Ptr: FuncTablePtr, const TRAIT_N: usize, const FUNC_N: usize> {
+    bases: [SubTable<Ptr, FUNC_N>; TRAIT_N],
+}
+
+impl<Ptr: FuncTablePtr, const TRAIT_N: usize, const FUNC_N: usize> CppTable<Ptr, TRAIT_N, FUNC_N> {
+    pub fn new(funcs: &[Function<Ptr>]) -> Self {
+        assert!(
+            funcs.len() <= TRAIT_N * FUNC_N,
+            "slice has size {}, maximum is {}",
+            funcs.len(),
+            TRAIT_N * FUNC_N
+        );
+        let mut bases = [SubTable {
+            offset_to_top: 0,
+            funcs: [filler_function::<Ptr>; FUNC_N],
+        }; TRAIT_N];
+        for (t, base) in bases.iter_mut().enumerate() {
+            base.offset_to_top = -((t * size_of::<usize>()) as isize);
+        }
+        for (base, chunk) in bases.iter_mut().zip(funcs.chunks(FUNC_N)) {
+            base.funcs[..chunk.len()].copy_from_slice(chunk);
+        }
+        Self { bases }
+    }
+}
+
+impl<Ptr: FuncTablePtr, const TRAIT_N: usize, const FUNC_N: usize> TableFootprint
+    for CppTable<Ptr, TRAIT_N, FUNC_N>
+{
+    /// The `vptr`s are in the objects, `TRAIT_N` words each, and are not counted.
+    fn footprint(&self) -> Footprint {
+        Footprint {
+            pointer_bytes: size_of::<Ptr>(),
+            table_bytes: size_of::<Self>(),
+            heap_bytes: 0,
+            slots: TRAIT_N * FUNC_N,
+            filler_slots: self
+                .bases
+                .iter()
+                .map(|b| count_fillers::<Ptr>(&b.funcs))
+                .sum(),
+        }
+    }
+}
+
+/// The `vptr`s of an object, one per base. A real object would interleave them with fields.
+#[repr(C)]
+pub struct CppObject<Ptr: FuncTablePtr, const TRAIT_N: usize, const FUNC_N: usize> {
+    vptrs: [*const SubTable<Ptr, FUNC_N>; TRAIT_N],
+}
+
+impl<Ptr: FuncTablePtr, const TRAIT_N: usize, const FUNC_N: usize> CppObject<Ptr, TRAIT_N, FUNC_N> {
+    pub fn new(table: &CppTable<Ptr, TRAIT_N, FUNC_N>) -> Self {
+        let mut vptrs = [table.bases.as_ptr(); TRAIT_N];
+        for (vptr, base) in vptrs.iter_mut().zip(table.bases.iter()) {
+            *vptr = base;
+        }
+        Self { vptrs }
+    }
+}
+
+/// Points to the complete object.
+#[repr(C)]
+pub struct CppPtr<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> {
+    /// The first `vptr` of the object.
+    pub(crate) this: *const *const SubTable<Self, FUNC_N>,
+    phantom: PhantomData<&'vt CppObject<Self, TRAIT_N, FUNC_N>>,
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize>
+    CppPtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    pub fn new(object: &'vt CppObject<Self, TRAIT_N, FUNC_N>) -> Self {
+        Self {
+            this: object.vptrs.as_ptr(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns a pointer to the sub-object of the `trait_n`-th base.
+    ///
+    /// # Safety
+    ///
+    /// Behavior is undefined if the object has fewer than `trait_n` bases.
+    #[inline(always)]
+    pub unsafe fn upcast(self, trait_n: u32) -> CppBasePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N> {
+        CppBasePtr {
+            this: self.this.add(trait_n as usize),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> Copy
+    for CppPtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+}
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> Clone
+    for CppPtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> FuncTablePtr
+    for CppPtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    type Arg = ArgT;
+    type Ret = RetT;
+    type Markers = ();
+    #[inline(always)]
+    unsafe fn get_function(&self, n: u32) -> Function<Self> {
+        let trait_n = n / (FUNC_N as u32);
+        let trait_func_n = n % (FUNC_N as u32);
+        let vptr = *self.this.add(trait_n as usize);
+        *(*vptr).funcs.get_unchecked(trait_func_n as usize)
+    }
+}
+
+/// Points to the sub-object of one base, as returned by `CppPtr::upcast`.
+#[repr(C)]
+pub struct CppBasePtr<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> {
+    /// The `vptr` of the base.
+    pub(crate) this: *const *const SubTable<CppPtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>, FUNC_N>,
+    phantom: PhantomData<&'vt ()>,
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize>
+    CppBasePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    /// Calls the `trait_func_n`-th method of the base on `data`.
+    ///
+    /// # Safety
+    ///
+    /// Behavior is undefined if the base has fewer than `trait_func_n` methods.
+    #[inline(always)]
+    pub unsafe fn call(self, trait_func_n: u32, data: *const (), arg: ArgT) -> RetT {
+        (**self.this).funcs.get_unchecked(trait_func_n as usize)(data, arg)
+    }
+
+    /// Returns a pointer to the complete object, the adjustment a thunk would make.
+    #[inline(always)]
+    pub fn top(self) -> CppPtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N> {
+        let sub_table = unsafe { &**self.this };
+        let top = unsafe { (self.this as *const u8).offset(sub_table.offset_to_top) };
+        CppPtr {
+            this: top as *const _,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> Copy
+    for CppBasePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+}
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> Clone
+    for CppBasePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
@@ -0,0 +1,184 @@
+//! Row-displacement compression of the global `type × selector` dispatch matrix.
+//!
+//! Each method of each trait has a selector, its index in the sums of traits this crate uses:
+//! `trait_n * FUNC_N + trait_func_n`. The matrix has a row per type and a column per selector,
+//! mostly empty when types implement few traits. Its rows are stored in a single array, each at
+//! the first offset where its methods land on free entries, so that rows interleave.
+//!
+//! A pointer points to the row of its type in the array: a call is an add and a load, as for
+//! `VPtr`. Hiding the first traits moves the pointer further in the row.
+use std::{
+    marker::PhantomData,
+    mem::{size_of, size_of_val},
+};
+
+use crate::{
+    filler_function,
+    footprint::{count_fillers, Footprint, TableFootprint},
+    FuncTablePtr, Function, ProjectPtr,
+};
+
+pub struct DisplacementTable<Ptr: FuncTablePtr> {
+    /// The rows of all types, interleaved. Free entries hold `filler_function`.
+    funcs: Vec<Function<Ptr>>,
+    /// Whether each entry of `funcs` belongs to a row.
+    used: Vec<bool>,
+    /// The offset of the row of each type in `funcs`, indexed by type id.
+    rows: Vec<usize>,
+}
+
+impl<Ptr: FuncTablePtr> DisplacementTable<Ptr> {
+    pub fn new() -> Self {
+        Self {
+            funcs: Vec::new(),
+            used: Vec::new(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Adds the row of a type, `None` where the type lacks the selector, and returns its id.
+    pub fn add_type(&mut self, row: &[Option<Function<Ptr>>]) -> u32 {
+        let fits = |offset: usize| {
+            row.iter()
+                .enumerate()
+                .all(|(selector, func)| func.is_none() || !self.is_used(offset + selector))
+        };
+        let offset = (0..).find(|offset| fits(*offset)).unwrap();
+        let end = offset + row.len();
+        if self.funcs.len() < end {
+            self.funcs.resize(end, filler_function::<Ptr>);
+            self.used.resize(end, false);
+        }
+        for (selector, func) in row.iter().enumerate() {
+            if let Some(func) = func {
+                self.funcs[offset + selector] = *func;
+                self.used[offset + selector] = true;
+            }
+        }
+        self.rows.push(offset);
+        (self.rows.len() - 1) as u32
+    }
+
+    fn is_used(&self, index: usize) -> bool {
+        self.used.get(index).copied().unwrap_or(false)
+    }
+}
+
+impl<Ptr: FuncTablePtr> Default for DisplacementTable<Ptr> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Ptr: FuncTablePtr> TableFootprint for DisplacementTable<Ptr> {
+    /// Counts the whole array, shared by all types.
+    fn footprint(&self) -> Footprint {
+        Footprint {
+            pointer_bytes: size_of::<Ptr>(),
+            table_bytes: size_of_val(self),
+            heap_bytes: self.funcs.capacity() * size_of::<Function<Ptr>>()
+                + self.used.capacity() * size_of::<bool>()
+                + self.rows.capacity() * size_of::<usize>(),
+            slots: self.funcs.len(),
+            filler_slots: count_fillers::<Ptr>(&self.funcs),
+        }
+    }
+}
+
+#[repr(C)]
+pub struct DisplacementPtr<'vt, ArgT, RetT, const FUNC_N: usize> {
+    /// The entry of selector 0 in the row, possibly out of the array if the type lacks it.
+    pub(crate) row: *const Function<Self>,
+    phantom: PhantomData<&'vt DisplacementTable<Self>>,
+}
+
+impl<'vt, ArgT, RetT, const FUNC_N: usize> DisplacementPtr<'vt, ArgT, RetT, FUNC_N> {
+    pub fn new(table: &'vt DisplacementTable<Self>, type_id: u32) -> Self {
+        Self {
+            row: table
+                .funcs
+                .as_ptr()
+                .wrapping_add(table.rows[type_id as usize]),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'vt, ArgT, RetT, const FUNC_N: usize> Copy for DisplacementPtr<'vt, ArgT, RetT, FUNC_N> {}
+impl<'vt, ArgT, RetT, const FUNC_N: usize> Clone for DisplacementPtr<'vt, ArgT, RetT, FUNC_N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'vt, ArgT, RetT, const FUNC_N: usize> FuncTablePtr
+    for DisplacementPtr<'vt, ArgT, RetT, FUNC_N>
+{
+    type Arg = ArgT;
+    type Ret = RetT;
+    type Markers = ();
+    #[inline(always)]
+    unsafe fn get_function(&self, n: u32) -> Function<Self> {
+        *self.row.add(n as usize)
+    }
+}
+
+impl<'vt, ArgT, RetT, const FUNC_N: usize> ProjectPtr for DisplacementPtr<'vt, ArgT, RetT, FUNC_N> {
+    /// # Panics
+    ///
+    /// Panics if the traits of `mask` are not contiguous: only offsetting the selectors is
+    /// supported, not renumbering them.
+    #[inline(always)]
+    unsafe fn project(&self, mask: usize) -> Self {
+        let first = mask.trailing_zeros();
+        // An empty mask hides every trait: there is no run, and the row is never read.
+        let run = mask.checked_shr(first).unwrap_or(0);
+        assert!(
+            run & (run + 1) == 0,
+            "traits {:#b} are not contiguous",
+            mask
+        );
+        Self {
+            row: self.row.wrapping_add(first as usize * FUNC_N),
+            phantom: PhantomData,
+        }
+    }
+}
+
+#[test]
+fn displacement_project_test() {
+    fn returns<const N: u64>(_: *const (), _: u64) -> u64 {
+        N
+    }
+
+    let mut table = DisplacementTable::new();
+    table.add_type(&[
+        Some(returns::<0>),
+        Some(returns::<1>),
+        Some(returns::<2>),
+        Some(returns::<3>),
+        Some(returns::<4>),
+        Some(returns::<5>),
+    ]);
+    let ptr = DisplacementPtr::<u64, u64, 2>::new(&table, 0);
+    let call =
+        |ptr: DisplacementPtr<u64, u64, 2>, n| unsafe { ptr.get_function(n)(std::ptr::null(), 0) };
+    let last = unsafe { ptr.project(0b110) };
+    assert!(call(last, 0) == 2);
+    assert!(call(last, 3) == 5);
+    // Hiding every trait is allowed, even if nothing can be called.
+    unsafe { ptr.project(0) };
+}
+
+#[test]
+#[should_panic(expected = "traits 0b101 are not contiguous")]
+fn displacement_project_not_contiguous_test() {
+    fn returns(_: *const (), _: u64) -> u64 {
+        0
+    }
+
+    let mut table = DisplacementTable::new();
+    table.add_type(&[Some(returns as fn(*const (), u64) -> u64); 3]);
+    let ptr = DisplacementPtr::<_, _, 1>::new(&table, 0);
+    unsafe { ptr.project(0b101) };
+}
@@ -1,71 +1,149 @@
+use std::cell::Cell;
+
 use crate::{
-    inline_sieve_table::InlineSieveTable, multi_ptrs::MultiVTable,
-    packed_sieve_table::PackedSieveTable, v_table::VTable, FuncTablePtr,
+    cpp_table::CppTable,
+    displacement::DisplacementTable,
+    filler_function,
+    inline_sieve_table::InlineSieveTable,
+    itab::ItabCache,
+    multi_ptrs::MultiVTable,
+    packed_sieve_table::PackedSieveTable,
+    perfect_hash::PerfectHashTable,
+    rel_packed_sieve_table::RelPackedSieveTable,
+    rel_v_table::{ArenaRef, RelVTable},
+    split_sieve::SplitSieveTable,
+    trait_search::TraitSearchTable,
+    v_table::VTable,
+    FuncTablePtr, Function, TraitId,
 };
 
 mod funcptrs {
+    use super::Fibonacci;
     use crate::FuncTablePtr;
 
     /// Returns fib(n).
-    pub fn fibonacci_00<I>(index: I, n: u64) -> u64
+    pub fn fibonacci_00<I>(data: *const (), n: u64) -> u64
     where
         I: FuncTablePtr<Arg = u64, Ret = u64>,
     {
+        let this = unsafe { Fibonacci::<I>::from_data(data) };
         match n {
-            0 => {
-                let fib_02 = unsafe { index.get_function(2) };
-                fib_02(index, n)
-            }
-            1 => {
-                let fib_04 = unsafe { index.get_function(4) };
-                fib_04(index, n)
-            }
-            n => {
-                let fib_06 = unsafe { index.get_function(6) };
-                let fib_07 = unsafe { index.get_function(7) };
-                 fib_06(index, n) + fib_07(index, n)
-            }
+            0 => unsafe { this.call(2, n) },
+            1 => unsafe { this.call(4, n) },
+            n => unsafe { this.call(6, n) + this.call(7, n) },
         }
     }
 
-    declare_filler!(fibonacci_01, u64, u64);
-
-    /// Returns fib(0) == 0.
-    pub fn fibonacci_02<I>(_index: I, _n: u64) -> u64
+    /// Like `fibonacci_00`, looking fib(n) up in the memo of the object first, and storing it
+    /// there once computed.
+    pub fn fibonacci_memo_00<I>(data: *const (), n: u64) -> u64
     where
         I: FuncTablePtr<Arg = u64, Ret = u64>,
     {
+        let this = unsafe { Fibonacci::<I>::from_data(data) };
+        if let Some(fib) = this.memoized(n) {
+            return fib;
+        }
+        let fib = fibonacci_00::<I>(data, n);
+        this.memoize(n, fib);
+        fib
+    }
+
+    declare_filler!(fibonacci_01, u64, u64);
+
+    /// Returns fib(0) == 0.
+    pub fn fibonacci_02(_data: *const (), _n: u64) -> u64 {
         0
     }
 
     declare_filler!(fibonacci_03, u64, u64);
 
     /// Returns fib(1) == 1.
-    pub fn fibonacci_04<I>(_index: I, _n: u64) -> u64
-    where
-        I: FuncTablePtr<Arg = u64, Ret = u64>,
-    {
+    pub fn fibonacci_04(_data: *const (), _n: u64) -> u64 {
         1
     }
 
     declare_filler!(fibonacci_05, u64, u64);
 
     /// Returns fib(n-1).
-    pub fn fibonacci_06<I>(index: I, n: u64) -> u64
+    pub fn fibonacci_06<I>(data: *const (), n: u64) -> u64
     where
         I: FuncTablePtr<Arg = u64, Ret = u64>,
     {
-        let a0_00 = unsafe { index.get_function(0) };
-        a0_00(index, n - 1)
+        unsafe { Fibonacci::<I>::from_data(data).call(0, n - 1) }
     }
 
     /// Returns fib(n-2).
-    pub fn fibonacci_07<I>(index: I, n: u64) -> u64
+    pub fn fibonacci_07<I>(data: *const (), n: u64) -> u64
     where
         I: FuncTablePtr<Arg = u64, Ret = u64>,
     {
-        let a0_00 = unsafe { index.get_function(0) };
-        a0_00(index, n - 2)
+        unsafe { Fibonacci::<I>::from_data(data).call(0, n - 2) }
+    }
+}
+
+/// The object the fibonacci functions are the methods of.
+///
+/// The methods receive a pointer to it and pair it with `ptr` again to call each other, as
+/// `self.other()` does through a `&dyn`. With the table of `make_fibonacci_memo_vtable`, the
+/// object remembers the values it computed, which makes `fibonacci` linear instead of exponential.
+pub struct Fibonacci<I> {
+    ptr: I,
+    /// `fib(n)` at index `n`, once computed by `fibonacci_memo_00`. Empty if built by `new`.
+    memo: Vec<Cell<Option<u64>>>,
+}
+
+impl<I> Fibonacci<I>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    pub fn new(ptr: I) -> Self {
+        Self {
+            ptr,
+            memo: Vec::new(),
+        }
+    }
+
+    /// Like `new`, remembering `fib(n)` for `n` below `memo_n`.
+    pub fn with_memo(ptr: I, memo_n: usize) -> Self {
+        Self {
+            ptr,
+            memo: vec![Cell::new(None); memo_n],
+        }
+    }
+
+    /// # Safety
+    ///
+    /// Behavior is undefined unless `data` points to a `Fibonacci<I>`.
+    unsafe fn from_data<'a>(data: *const ()) -> &'a Self {
+        &*(data as *const Self)
+    }
+
+    /// Calls the `n`-th method of the object.
+    ///
+    /// # Safety
+    ///
+    /// Behavior is undefined if the pointer knows about fewer than `n` functions.
+    #[inline(always)]
+    pub unsafe fn call(&self, n: u32, arg: u64) -> u64 {
+        self.ptr.get_function(n)(self as *const Self as *const (), arg)
+    }
+
+    /// Returns the values computed so far, `None` for those not computed or not remembered.
+    pub fn memo(&self) -> Vec<Option<u64>> {
+        self.memo.iter().map(Cell::get).collect()
+    }
+
+    #[inline(always)]
+    fn memoized(&self, n: u64) -> Option<u64> {
+        self.memo.get(n as usize).and_then(Cell::get)
+    }
+
+    #[inline(always)]
+    fn memoize(&self, n: u64, fib: u64) {
+        if let Some(cell) = self.memo.get(n as usize) {
+            cell.set(Some(fib));
+        }
     }
 }
 
@@ -75,22 +153,77 @@ where
 {
     use funcptrs::*;
     VTable::new([
-        fibonacci_00,
+        fibonacci_00::<I>,
         fibonacci_01,
         fibonacci_02,
         fibonacci_03,
         fibonacci_04,
         fibonacci_05,
-        fibonacci_06,
-        fibonacci_07,
+        fibonacci_06::<I>,
+        fibonacci_07::<I>,
     ])
 }
 
+/// Like `make_fibonacci_vtable`, remembering the values in the memo of the object.
+///
+/// Only objects built by `Fibonacci::with_memo` remember them: the others have an empty memo.
+pub fn make_fibonacci_memo_vtable<I>() -> VTable<I, 8>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    use funcptrs::*;
+    VTable::new([
+        fibonacci_memo_00::<I>,
+        fibonacci_01,
+        fibonacci_02,
+        fibonacci_03,
+        fibonacci_04,
+        fibonacci_05,
+        fibonacci_06::<I>,
+        fibonacci_07::<I>,
+    ])
+}
+
+/// The fibonacci functions, in order.
+fn fibonacci_funcs<I>() -> [Function<I>; 8]
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    use funcptrs::*;
+    [
+        fibonacci_00::<I>,
+        fibonacci_01,
+        fibonacci_02,
+        fibonacci_03,
+        fibonacci_04,
+        fibonacci_05,
+        fibonacci_06::<I>,
+        fibonacci_07::<I>,
+    ]
+}
+
+/// Like `make_fibonacci_vtable`, with fillers after the fibonacci functions up to `N`.
+pub fn make_fibonacci_vtable_sized<I, const N: usize>() -> VTable<I, N>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    VTable::new_from_slice(&fibonacci_funcs::<I>())
+}
+
+/// Like `make_fibonacci_vtable_sized`, with 32-bit offsets.
+pub fn make_fibonacci_rel_vtable_sized<I, const N: usize>() -> ArenaRef<RelVTable<I, N>>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    RelVTable::new_from_slice(&fibonacci_funcs::<I>())
+}
+
+/// Returns fib(n), calling the methods through `index`.
 pub fn fibonacci<I>(index: I, n: u64) -> u64
 where
     I: FuncTablePtr<Arg = u64, Ret = u64>,
 {
-    unsafe { index.get_function(0)(index, n) }
+    unsafe { Fibonacci::new(index).call(0, n) }
 }
 
 pub fn fibonacci_vtable(n: u64) -> u64 {
@@ -102,11 +235,39 @@ pub fn fibonacci_vtable(n: u64) -> u64 {
 
 #[test]
 fn fibonacci_vptr_test() {
+    fn fibonacci_vtable_sized<const N: usize>(n: u64) -> u64 {
+        use crate::v_table::VPtr;
+
+        let table = make_fibonacci_vtable_sized::<_, N>();
+        let ptr = VPtr::new(&table);
+        fibonacci(ptr, n)
+    }
+
     for n in 0..10 {
         assert!(fibonacci_vtable(n) == fibonacci_fast(n));
+        assert!(fibonacci_vtable_sized::<16>(n) == fibonacci_fast(n));
     }
 }
 
+#[test]
+fn fibonacci_memo_test() {
+    use crate::v_table::VPtr;
+
+    let table = make_fibonacci_memo_vtable();
+    // Exponential without the memo.
+    let object = Fibonacci::with_memo(VPtr::new(&table), 61);
+    assert!(unsafe { object.call(0, 60) } == fibonacci_fast(60));
+    let expected: Vec<_> = (0..61).map(|n| Some(fibonacci_fast(n))).collect();
+    assert!(object.memo() == expected, "{:?}", object.memo());
+    // Without a memo, the object computes the same.
+    assert!(unsafe { Fibonacci::new(VPtr::new(&table)).call(0, 20) } == fibonacci_fast(20));
+    let plain_table = make_fibonacci_vtable();
+    let object = Fibonacci::with_memo(VPtr::new(&plain_table), 21);
+    assert!(unsafe { object.call(0, 20) } == fibonacci_fast(20));
+    // The functions of the benches leave the memo alone.
+    assert!(object.memo() == vec![None; 21]);
+}
+
 pub fn make_fibonacci_multiptr<I, const TRAIT_N: usize, const FUNC_N: usize>(
 ) -> MultiVTable<I, TRAIT_N, FUNC_N>
 where
@@ -115,14 +276,14 @@ where
     use crate::multi_ptrs::*;
     use funcptrs::*;
     MultiVTable::<I, TRAIT_N, FUNC_N>::new(&[
-        fibonacci_00,
+        fibonacci_00::<I>,
         fibonacci_01,
         fibonacci_02,
         fibonacci_03,
         fibonacci_04,
         fibonacci_05,
-        fibonacci_06,
-        fibonacci_07,
+        fibonacci_06::<I>,
+        fibonacci_07::<I>,
     ])
 }
 
@@ -148,7 +309,7 @@ where
 {
     use funcptrs::*;
     InlineSieveTable::new([
-        Some(fibonacci_00),
+        Some(fibonacci_00::<I>),
         None,
         Some(fibonacci_01),
         Some(fibonacci_02),
@@ -158,11 +319,51 @@ where
         None,
         Some(fibonacci_05),
         None,
-        Some(fibonacci_06),
-        Some(fibonacci_07),
+        Some(fibonacci_06::<I>),
+        Some(fibonacci_07::<I>),
     ])
 }
 
+/// `TRAIT_N` traits of `FUNC_N` functions, each trait followed by a slot the pointer cannot see.
+///
+/// `N` must be `TRAIT_N * (FUNC_N + 1)`: it can't be computed from the other parameters in stable
+/// Rust.
+pub fn make_fibonacci_inline_sieve_sized<
+    I,
+    const TRAIT_N: usize,
+    const FUNC_N: usize,
+    const N: usize,
+>() -> InlineSieveTable<I, N>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    assert!(
+        N == TRAIT_N * (FUNC_N + 1),
+        "N is {}, expected {}",
+        N,
+        TRAIT_N * (FUNC_N + 1)
+    );
+    let funcs = fibonacci_funcs::<I>();
+    assert!(TRAIT_N * FUNC_N >= funcs.len(), "not enough slots");
+    let mut opt_funcs: [Option<Function<I>>; N] = [None; N];
+    let mut traits = [(0, 0); TRAIT_N];
+    for (n, func) in funcs
+        .iter()
+        .copied()
+        .chain(std::iter::repeat(filler_function::<I> as Function<I>))
+        .take(TRAIT_N * FUNC_N)
+        .enumerate()
+    {
+        let (trait_n, trait_func_n) = (n / FUNC_N, n % FUNC_N);
+        opt_funcs[trait_n * (FUNC_N + 1) + trait_func_n] = Some(func);
+        traits[trait_n].1 |= 1 << (trait_n * (FUNC_N + 1) + trait_func_n);
+    }
+    for (trait_n, id) in fibonacci_trait_ids::<TRAIT_N>().iter().enumerate() {
+        traits[trait_n].0 = *id;
+    }
+    InlineSieveTable::new_with_traits(opt_funcs, &traits)
+}
+
 #[test]
 fn fibonacci_inline_sieve_test() {
     pub fn fibonacci_inline_sieve(n: u64) -> u64 {
@@ -172,8 +373,20 @@ fn fibonacci_inline_sieve_test() {
         fibonacci(ptr, n)
     }
 
+    fn fibonacci_inline_sieve_sized<const TRAIT_N: usize, const FUNC_N: usize, const N: usize>(
+        n: u64,
+    ) -> u64 {
+        use crate::inline_sieve_table::InlineSievePtr;
+        let table = make_fibonacci_inline_sieve_sized::<_, TRAIT_N, FUNC_N, N>();
+        let ptr = InlineSievePtr::new(&table);
+        fibonacci(ptr, n)
+    }
+
     for n in 0..10 {
         assert!(fibonacci_inline_sieve(n) == fibonacci_fast(n));
+        assert!(fibonacci_inline_sieve_sized::<1, 8, 9>(n) == fibonacci_fast(n));
+        assert!(fibonacci_inline_sieve_sized::<3, 3, 12>(n) == fibonacci_fast(n));
+        assert!(fibonacci_inline_sieve_sized::<16, 1, 32>(n) == fibonacci_fast(n));
     }
 }
 
@@ -183,16 +396,324 @@ where
     I: FuncTablePtr<Arg = u64, Ret = u64>,
 {
     use funcptrs::*;
-    PackedSieveTable::<I, TRAIT_N, FUNC_N>::new(&[
-        fibonacci_00,
-        fibonacci_01,
-        fibonacci_02,
-        fibonacci_03,
-        fibonacci_04,
-        fibonacci_05,
-        fibonacci_06,
-        fibonacci_07,
-    ])
+    PackedSieveTable::<I, TRAIT_N, FUNC_N>::new_with_trait_ids(
+        &[
+            fibonacci_00::<I>,
+            fibonacci_01,
+            fibonacci_02,
+            fibonacci_03,
+            fibonacci_04,
+            fibonacci_05,
+            fibonacci_06::<I>,
+            fibonacci_07::<I>,
+        ],
+        fibonacci_trait_ids(),
+    )
+}
+
+/// Like `make_fibonacci_packed_sieve`, with 32-bit offsets.
+pub fn make_fibonacci_rel_packed_sieve<I, const TRAIT_N: usize, const FUNC_N: usize>(
+) -> RelPackedSieveTable<I, TRAIT_N, FUNC_N>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    RelPackedSieveTable::new_with_trait_ids(&fibonacci_funcs::<I>(), fibonacci_trait_ids())
+}
+
+/// Like `make_fibonacci_packed_sieve`, for a sieve split with the data pointer.
+pub fn make_fibonacci_split_sieve<I, const TRAIT_N: usize, const FUNC_N: usize>(
+) -> SplitSieveTable<I, TRAIT_N, FUNC_N>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    SplitSieveTable::new_with_trait_ids(&fibonacci_funcs::<I>(), fibonacci_trait_ids())
+}
+
+#[test]
+fn fibonacci_rel_test() {
+    use crate::{rel_packed_sieve_table::RelPackedSievePtr, rel_v_table::RelVPtr};
+
+    fn fibonacci_rel_vtable<const N: usize>(n: u64) -> u64 {
+        let table = make_fibonacci_rel_vtable_sized::<_, N>();
+        fibonacci(RelVPtr::new(&table), n)
+    }
+    fn fibonacci_rel_packed_sieve<const TRAIT_N: usize, const FUNC_N: usize>(n: u64) -> u64 {
+        let table = make_fibonacci_rel_packed_sieve::<_, TRAIT_N, FUNC_N>();
+        fibonacci(RelPackedSievePtr::new(&table), n)
+    }
+    for n in 0..10 {
+        assert!(fibonacci_rel_vtable::<8>(n) == fibonacci_fast(n));
+        assert!(fibonacci_rel_vtable::<16>(n) == fibonacci_fast(n));
+        assert!(fibonacci_rel_packed_sieve::<1, 10>(n) == fibonacci_fast(n));
+        assert!(fibonacci_rel_packed_sieve::<4, 3>(n) == fibonacci_fast(n));
+        assert!(fibonacci_rel_packed_sieve::<5, 2>(n) == fibonacci_fast(n));
+    }
+}
+
+/// Returns a cache with a single type, the fibonacci functions, spread over `TRAIT_N` traits.
+///
+/// The type has id 0 and the traits ids `0..TRAIT_N`.
+pub fn make_fibonacci_itab_cache<I, const TRAIT_N: usize, const FUNC_N: usize>(
+) -> ItabCache<I, FUNC_N>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    let funcs = fibonacci_funcs::<I>();
+    let mut cache = ItabCache::new();
+    cache.add_type(&funcs);
+    for trait_n in 0..TRAIT_N {
+        let methods: Vec<u32> = (trait_n * FUNC_N..(trait_n + 1) * FUNC_N)
+            .filter(|m| *m < funcs.len())
+            .map(|m| m as u32)
+            .collect();
+        cache.add_trait(&methods);
+    }
+    cache
+}
+
+#[test]
+fn fibonacci_itab_test() {
+    use crate::itab::ItabPtr;
+
+    fn fibonacci_itab<const TRAIT_N: usize, const FUNC_N: usize>(n: u64) -> u64 {
+        let cache = make_fibonacci_itab_cache::<_, TRAIT_N, FUNC_N>();
+        fibonacci(ItabPtr::new(&cache, 0), n)
+    }
+    for n in 0..10 {
+        assert!(fibonacci_itab::<1, 8>(n) == fibonacci_fast(n));
+        assert!(fibonacci_itab::<3, 3>(n) == fibonacci_fast(n));
+        assert!(fibonacci_itab::<16, 1>(n) == fibonacci_fast(n));
+    }
+
+    // Tables are only built for the traits that are called.
+    use crate::footprint::TableFootprint;
+    let cache = make_fibonacci_itab_cache::<_, 3, 3>();
+    assert!(cache.footprint().slots == 0);
+    fibonacci(ItabPtr::new(&cache, 0), 1);
+    assert!(cache.footprint().slots == 2 * 3);
+    fibonacci(ItabPtr::new(&cache, 0), 5);
+    assert!(cache.footprint().slots == 3 * 3);
+}
+
+/// Passes the pairs of `(TRAIT_N, FUNC_N)` for multi-trait representations to a macro, after its
+/// arguments: `multi_trait_matrix!(sweep!(bench,))` expands to `sweep!(bench, (1, 8), (2, 4), …)`.
+/// Shared by `benches/fibonacci.rs` and the `footprint` binary.
+///
+/// The 8 fibonacci functions are spread over the traits: in a single trait, as densely as possible
+/// over several, then with 8 methods per trait so that all functions end up in the first trait.
+/// `PackedSievePtr` supports at most 8 traits, the others go beyond with `beyond_8`.
+/// `InlineSievePtr` supports at most 64 slots, one per method plus one per trait, so
+/// `inline_sieve` skips `(8, 8)`.
+#[macro_export]
+macro_rules! multi_trait_matrix {
+    ($macro:ident!($($args:tt)*)) => {
+        $macro!(
+            $($args)*
+            (1, 8), (2, 4), (3, 3), (4, 2), (5, 2), (6, 2), (7, 2), (8, 1),
+            (2, 8), (3, 8), (4, 8), (5, 8), (6, 8), (7, 8), (8, 8)
+        )
+    };
+    ($macro:ident!($($args:tt)*), beyond_8) => {
+        $macro!(
+            $($args)*
+            (1, 8), (2, 4), (3, 3), (4, 2), (5, 2), (6, 2), (7, 2), (8, 1),
+            (2, 8), (3, 8), (4, 8), (5, 8), (6, 8), (7, 8), (8, 8),
+            (12, 1), (16, 1)
+        )
+    };
+    ($macro:ident!($($args:tt)*), inline_sieve) => {
+        $macro!(
+            $($args)*
+            (1, 8), (2, 4), (3, 3), (4, 2), (5, 2), (6, 2), (7, 2), (8, 1),
+            (2, 8), (3, 8), (4, 8), (5, 8), (6, 8), (7, 8),
+            (12, 1), (16, 1)
+        )
+    };
+}
+
+/// The ids of the fibonacci traits, sparse so that they must be searched.
+pub fn fibonacci_trait_ids<const TRAIT_N: usize>() -> [TraitId; TRAIT_N] {
+    let mut ids = [0; TRAIT_N];
+    for (trait_n, id) in ids.iter_mut().enumerate() {
+        *id = 7 * trait_n as u32 + 3;
+    }
+    ids
+}
+
+/// Returns the fibonacci functions spread over traits with ids `fibonacci_trait_ids()`, listed
+/// in reverse order.
+pub fn make_fibonacci_trait_search<I, const TRAIT_N: usize, const FUNC_N: usize>(
+) -> TraitSearchTable<I, FUNC_N>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    let funcs = fibonacci_funcs::<I>();
+    let mut chunks = funcs.chunks(FUNC_N);
+    let mut traits: Vec<_> = fibonacci_trait_ids::<TRAIT_N>()
+        .iter()
+        .map(|id| (*id, VTable::new_from_slice(chunks.next().unwrap_or(&[]))))
+        .collect();
+    traits.reverse();
+    TraitSearchTable::new(traits)
+}
+
+#[test]
+fn fibonacci_trait_search_test() {
+    use crate::{trait_search::TraitSearchPtr, ProjectPtr};
+
+    fn fibonacci_trait_search<const TRAIT_N: usize, const FUNC_N: usize>(n: u64) -> u64 {
+        let table = make_fibonacci_trait_search::<_, TRAIT_N, FUNC_N>();
+        let ptr = TraitSearchPtr::new(&table, fibonacci_trait_ids::<TRAIT_N>()).unwrap();
+        fibonacci(ptr, n)
+    }
+    for n in 0..10 {
+        assert!(fibonacci_trait_search::<1, 8>(n) == fibonacci_fast(n));
+        assert!(fibonacci_trait_search::<3, 3>(n) == fibonacci_fast(n));
+        assert!(fibonacci_trait_search::<16, 1>(n) == fibonacci_fast(n));
+    }
+
+    let table = make_fibonacci_trait_search::<_, 3, 3>();
+    assert!(TraitSearchPtr::new(&table, [3, 10, 11]).is_none());
+    // Hiding the first trait leaves the two others, which are enough for `n <= 1`.
+    let ptr = TraitSearchPtr::new(&table, [17, 3, 10]).unwrap();
+    let ptr = unsafe { ptr.project(0b110) };
+    for n in 0..2 {
+        assert!(fibonacci(ptr, n) == fibonacci_fast(n));
+    }
+}
+
+pub fn make_fibonacci_cpp<I, const TRAIT_N: usize, const FUNC_N: usize>(
+) -> CppTable<I, TRAIT_N, FUNC_N>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    CppTable::new(&fibonacci_funcs::<I>())
+}
+
+#[test]
+fn fibonacci_cpp_test() {
+    use crate::cpp_table::{CppObject, CppPtr};
+
+    fn fibonacci_cpp<const TRAIT_N: usize, const FUNC_N: usize>(n: u64) -> u64 {
+        let table = make_fibonacci_cpp::<_, TRAIT_N, FUNC_N>();
+        let object = CppObject::new(&table);
+        fibonacci(CppPtr::new(&object), n)
+    }
+    for n in 0..10 {
+        assert!(fibonacci_cpp::<1, 8>(n) == fibonacci_fast(n));
+        assert!(fibonacci_cpp::<3, 3>(n) == fibonacci_fast(n));
+        assert!(fibonacci_cpp::<16, 1>(n) == fibonacci_fast(n));
+    }
+
+    // `fibonacci_06`, fib(n-1), is the first method of the third base. Called through the base, it
+    // calls `fibonacci_00` through the pointer its object was paired with.
+    let table = make_fibonacci_cpp::<_, 3, 3>();
+    let object = CppObject::new(&table);
+    let ptr = CppPtr::new(&object);
+    let base = unsafe { ptr.upcast(2) };
+    let data = Fibonacci::new(ptr);
+    for n in 1..10 {
+        let data = &data as *const Fibonacci<_> as *const ();
+        assert!(unsafe { base.call(0, data, n) } == fibonacci_fast(n - 1));
+    }
+    assert!(fibonacci(base.top(), 9) == fibonacci_fast(9));
+}
+
+/// Returns the fibonacci functions followed by fillers, `FUNC_N` per trait, hashed over `SLOTS`.
+pub fn make_fibonacci_perfect_hash_sized<
+    I,
+    const TRAIT_N: usize,
+    const FUNC_N: usize,
+    const SLOTS: usize,
+>() -> PerfectHashTable<I, SLOTS>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    use crate::perfect_hash::selector;
+
+    let funcs = fibonacci_funcs::<I>();
+    assert!(TRAIT_N * FUNC_N >= funcs.len(), "not enough slots");
+    let methods: Vec<(u32, Function<I>)> = funcs
+        .iter()
+        .copied()
+        .chain(std::iter::repeat(filler_function::<I> as Function<I>))
+        .take(TRAIT_N * FUNC_N)
+        .enumerate()
+        .map(|(n, func)| (selector((n / FUNC_N) as u32, (n % FUNC_N) as u32), func))
+        .collect();
+    PerfectHashTable::new(&methods)
+}
+
+#[test]
+fn fibonacci_perfect_hash_test() {
+    use crate::perfect_hash::PerfectHashPtr;
+
+    fn fibonacci_perfect_hash<const TRAIT_N: usize, const FUNC_N: usize, const SLOTS: usize>(
+        n: u64,
+    ) -> u64 {
+        let table = make_fibonacci_perfect_hash_sized::<
+            PerfectHashPtr<_, _, FUNC_N, SLOTS>,
+            TRAIT_N,
+            FUNC_N,
+            SLOTS,
+        >();
+        fibonacci(PerfectHashPtr::new(&table), n)
+    }
+    for n in 0..10 {
+        assert!(fibonacci_perfect_hash::<1, 8, 16>(n) == fibonacci_fast(n));
+        assert!(fibonacci_perfect_hash::<3, 3, 16>(n) == fibonacci_fast(n));
+        assert!(fibonacci_perfect_hash::<12, 4, 128>(n) == fibonacci_fast(n));
+    }
+}
+
+/// Returns an array with the fibonacci row as type 0, spread over `TRAIT_N` traits.
+///
+/// `fibonacci_01`, `fibonacci_03` and `fibonacci_05` are never called, so the row has holes
+/// there, where the rows of other types can go.
+pub fn make_fibonacci_displacement<I, const TRAIT_N: usize, const FUNC_N: usize>(
+) -> DisplacementTable<I>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    let funcs = fibonacci_funcs::<I>();
+    assert!(TRAIT_N * FUNC_N >= funcs.len(), "not enough slots");
+    let row: Vec<Option<Function<I>>> = (0..funcs.len())
+        .map(|n| match n {
+            1 | 3 | 5 => None,
+            n => Some(funcs[n]),
+        })
+        .collect();
+    let mut table = DisplacementTable::new();
+    table.add_type(&row);
+    table
+}
+
+#[test]
+fn fibonacci_displacement_test() {
+    use crate::displacement::DisplacementPtr;
+
+    fn fibonacci_displacement<const TRAIT_N: usize, const FUNC_N: usize>(n: u64) -> u64 {
+        let table = make_fibonacci_displacement::<_, TRAIT_N, FUNC_N>();
+        fibonacci(DisplacementPtr::<_, _, FUNC_N>::new(&table, 0), n)
+    }
+    for n in 0..10 {
+        assert!(fibonacci_displacement::<1, 8>(n) == fibonacci_fast(n));
+        assert!(fibonacci_displacement::<3, 3>(n) == fibonacci_fast(n));
+        assert!(fibonacci_displacement::<16, 1>(n) == fibonacci_fast(n));
+    }
+
+    // A type with only the never called fillers fits in the holes of the fibonacci row.
+    use crate::footprint::TableFootprint;
+    let mut table = make_fibonacci_displacement::<_, 4, 2>();
+    let holes = fibonacci_funcs::<DisplacementPtr<u64, u64, 2>>().map(Some);
+    let holes = [None, holes[1], None, holes[3], None, holes[5]];
+    assert!(table.add_type(&holes) == 1);
+    assert!(table.footprint().slots == 8);
+    assert!(table.footprint().filler_slots == 0);
+    let ptr = DisplacementPtr::<_, _, 2>::new(&table, 0);
+    for n in 0..10 {
+        assert!(fibonacci(ptr, n) == fibonacci_fast(n));
+    }
 }
 
 #[test]
@@ -211,6 +732,88 @@ fn fibonacci_sieve_test() {
     }
 }
 
+#[test]
+fn fibonacci_split_sieve_test() {
+    use crate::{split_sieve::SplitSievePtr, ProjectPtr, QueryPtr};
+
+    // The functions get their data from `fibonacci`: the pointer carries a dummy.
+    let dummy = 0u64;
+    let fibonacci_split_sieve = |n| {
+        let table = make_fibonacci_split_sieve::<_, 5, 2>();
+        fibonacci(SplitSievePtr::new(&dummy, &table), n)
+    };
+    for n in 0..10 {
+        assert!(fibonacci_split_sieve(n) == fibonacci_fast(n));
+    }
+
+    let table = make_fibonacci_split_sieve::<_, 3, 3>();
+    let ptr = SplitSievePtr::new(&dummy, &table);
+    for n in 0..10 {
+        assert!(fibonacci(ptr, n) == fibonacci_fast(n));
+    }
+    let third = unsafe { ptr.project(0b100) };
+    let second = third.query(10).unwrap();
+    assert!(unsafe { Fibonacci::new(second).call(1, 0) } == 1);
+}
+
+#[test]
+fn fibonacci_hybrid_sieve_test() {
+    use crate::{hybrid_sieve::HybridSievePtr, ProjectPtr, QueryPtr};
+
+    fn fibonacci_hybrid_sieve<const TRAIT_N: usize, const FUNC_N: usize>(n: u64) -> u64 {
+        let table = make_fibonacci_packed_sieve::<_, TRAIT_N, FUNC_N>();
+        fibonacci(HybridSievePtr::new(&table), n)
+    }
+    for n in 0..10 {
+        assert!(fibonacci_hybrid_sieve::<1, 10>(n) == fibonacci_fast(n));
+        assert!(fibonacci_hybrid_sieve::<4, 3>(n) == fibonacci_fast(n));
+        assert!(fibonacci_hybrid_sieve::<5, 2>(n) == fibonacci_fast(n));
+    }
+
+    // As in `fibonacci_query_test`, the primary trait following the projections.
+    let table = make_fibonacci_packed_sieve::<_, 3, 3>();
+    let ptr = HybridSievePtr::new(&table);
+    let third = unsafe { ptr.project(0b100) };
+    let second = third.query(10).unwrap();
+    assert!(unsafe { Fibonacci::new(second).call(1, 0) } == 1);
+}
+
+#[test]
+fn fibonacci_query_test() {
+    use crate::{
+        inline_sieve_table::InlineSievePtr, packed_sieve_table::PackedSievePtr,
+        rel_packed_sieve_table::RelPackedSievePtr, ProjectPtr, QueryPtr,
+    };
+
+    // The traits have ids 3, 10 and 17, `fibonacci_04` being the second method of the second.
+    let table = make_fibonacci_packed_sieve::<_, 3, 3>();
+    let ptr = PackedSievePtr::new(&table);
+    assert!(ptr.query(11).is_none());
+    let second = ptr.query(10).unwrap();
+    assert!(unsafe { Fibonacci::new(second).call(1, 0) } == 1);
+    // From the third trait to its sibling.
+    let third = unsafe { ptr.project(0b100) };
+    let second = third.query(10).unwrap();
+    assert!(unsafe { Fibonacci::new(second).call(1, 0) } == 1);
+
+    let table = make_fibonacci_inline_sieve_sized::<_, 3, 3, 12>();
+    let ptr = InlineSievePtr::new(&table);
+    assert!(ptr.query(11).is_none());
+    let second = ptr.query(10).unwrap();
+    assert!(unsafe { Fibonacci::new(second).call(1, 0) } == 1);
+    // The inline sieve projects over slots: the slots of the third trait are the 7th and 8th.
+    let third = unsafe { ptr.project(0b1100_0000) };
+    let second = third.query(10).unwrap();
+    assert!(unsafe { Fibonacci::new(second).call(1, 0) } == 1);
+
+    let table = make_fibonacci_rel_packed_sieve::<_, 3, 3>();
+    let ptr = RelPackedSievePtr::new(&table);
+    assert!(ptr.query(11).is_none());
+    let third = unsafe { ptr.project(0b100) };
+    let second = third.query(10).unwrap();
+    assert!(unsafe { Fibonacci::new(second).call(1, 0) } == 1);
+}
+
 #[cfg(test)]
 fn fibonacci_fast(n: u64) -> u64 {
     if n == 1 {
@@ -0,0 +1,80 @@
+//! How much memory a representation needs, beyond its pointer size.
+
+/// The memory used by a table and the pointers to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Footprint {
+    /// Size of a pointer to the table, without the data pointer a `Box` would add.
+    pub pointer_bytes: usize,
+    /// Size of the table itself, including alignment padding.
+    pub table_bytes: usize,
+    /// Size of what the table owns on the heap, or in the arena of relative tables.
+    pub heap_bytes: usize,
+    /// Number of function slots in the table, and in what it owns.
+    pub slots: usize,
+    /// Number of those slots holding `filler_function`.
+    pub filler_slots: usize,
+}
+
+impl Footprint {
+    /// Returns the memory used by the table, on the heap or not.
+    pub fn total_table_bytes(&self) -> usize {
+        self.table_bytes + self.heap_bytes
+    }
+}
+
+/// A table that can report its footprint.
+pub trait TableFootprint {
+    fn footprint(&self) -> Footprint;
+}
+
+/// Returns how many of `funcs` are `filler_function`.
+///
+/// Function addresses are not guaranteed to be unique, so a function could be mistaken for
+/// `filler_function` if the compiler merged them. This is good enough for a report.
+pub(crate) fn count_fillers<P: crate::FuncTablePtr>(funcs: &[crate::Function<P>]) -> usize {
+    let filler = crate::filler_function::<P> as crate::Function<P> as usize;
+    funcs.iter().filter(|f| **f as usize == filler).count()
+}
+
+#[test]
+fn fibonacci_footprint_test() {
+    use crate::fibonacci::{
+        make_fibonacci_inline_sieve_sized, make_fibonacci_multiptr, make_fibonacci_packed_sieve,
+        make_fibonacci_vtable_sized,
+    };
+    use crate::{
+        inline_sieve_table::InlineSievePtr, multi_ptrs::MultiVPtr,
+        packed_sieve_table::PackedSievePtr, v_table::VPtr,
+    };
+    let word = std::mem::size_of::<usize>();
+
+    let vtable = make_fibonacci_vtable_sized::<VPtr<u64, u64, 16>, 16>().footprint();
+    assert!(vtable.pointer_bytes == word);
+    assert!(vtable.table_bytes == 16 * word);
+    assert!((vtable.slots, vtable.filler_slots) == (16, 8));
+
+    let rel_vtable = crate::fibonacci::make_fibonacci_rel_vtable_sized::<
+        crate::rel_v_table::RelVPtr<u64, u64, 16>,
+        16,
+    >()
+    .footprint();
+    assert!(rel_vtable.table_bytes == 16 * 4);
+    assert!((rel_vtable.slots, rel_vtable.filler_slots) == (16, 8));
+
+    let multi = make_fibonacci_multiptr::<MultiVPtr<u64, u64, 3, 3>, 3, 3>().footprint();
+    assert!(multi.pointer_bytes == 3 * word);
+    assert!((multi.slots, multi.filler_slots) == (9, 1));
+
+    let packed = make_fibonacci_packed_sieve::<PackedSievePtr<u64, u64, 3, 3>, 3, 3>().footprint();
+    assert!(packed.pointer_bytes == word);
+    assert!(packed.table_bytes == 256);
+    assert!(packed.heap_bytes >= 3 * 3 * word);
+    assert!((packed.slots, packed.filler_slots) == (9, 1));
+
+    let inline =
+        make_fibonacci_inline_sieve_sized::<InlineSievePtr<u64, u64, 12>, 3, 3, 12>().footprint();
+    assert!(inline.pointer_bytes == 2 * word);
+    assert!((inline.slots, inline.filler_slots) == (12, 4));
+    // The ids and slots of the 3 traits.
+    assert!(inline.heap_bytes == 3 * 2 * word);
+}
@@ -0,0 +1,165 @@
+//! Like `packed_sieve_table`, with the table of the first visible trait resolved in the pointer.
+//!
+//! The pointer is two words: the `VTable` of its primary trait, the first one it sees, and the
+//! packed sieve. Calling a method of the primary trait loads from the first word, as `VPtr` does;
+//! the other traits go through the sieve, as with `PackedSievePtr`. Projecting resolves the table
+//! of the new primary trait, without allocating: put the most used trait first.
+use std::marker::PhantomData;
+
+use crate::{
+    packed_sieve_table::PackedSieveTable, v_table::VTable, FuncTable, FuncTablePtr, Function,
+    ProjectPtr, QueryPtr, TraitId,
+};
+
+#[repr(C)]
+pub struct HybridSievePtr<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> {
+    /// The table of the first visible trait.
+    pub(crate) primary: &'vt VTable<Self, FUNC_N>,
+    /// `*const PackedSieveTable<Self, TRAIT_N, FUNC_N> | sieve`, as in `PackedSievePtr`.
+    pub(crate) mangled: usize,
+    phantom: PhantomData<&'vt PackedSieveTable<Self, TRAIT_N, FUNC_N>>,
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize>
+    HybridSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    pub fn new(sieve_table: &'vt PackedSieveTable<Self, TRAIT_N, FUNC_N>) -> Self {
+        Self::with_sieve(sieve_table, sieve_table.pack())
+    }
+
+    /// Resolves the primary trait of `mangled`, the first trait of the table if it sees none.
+    #[inline(always)]
+    fn with_sieve(
+        sieve_table: &'vt PackedSieveTable<Self, TRAIT_N, FUNC_N>,
+        mangled: usize,
+    ) -> Self {
+        let sieve = mangled & !PackedSieveTable::<Self, TRAIT_N, FUNC_N>::SIEVE_MASK;
+        let first = if sieve == 0 {
+            0
+        } else {
+            sieve.trailing_zeros() as usize
+        };
+        Self {
+            primary: unsafe { &*sieve_table.traits_ptr[first] },
+            mangled,
+            phantom: PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn multi_v(&self) -> &'vt PackedSieveTable<Self, TRAIT_N, FUNC_N> {
+        PackedSieveTable::unpack(self.mangled)
+    }
+
+    /// Returns the `trait_func_n`-th function of the primary trait, as `VPtr::get_function`.
+    ///
+    /// # Safety
+    ///
+    /// Behavior is undefined if the trait has fewer than `trait_func_n` functions.
+    #[inline(always)]
+    pub unsafe fn get_primary_function(&self, trait_func_n: u32) -> Function<Self> {
+        self.primary.get_function(trait_func_n)
+    }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> Copy
+    for HybridSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+}
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> Clone
+    for HybridSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> FuncTablePtr
+    for HybridSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    type Arg = ArgT;
+    type Ret = RetT;
+    type Markers = ();
+    #[inline(always)]
+    unsafe fn get_function(&self, n: u32) -> Function<Self> {
+        if n < FUNC_N as u32 {
+            return self.get_primary_function(n);
+        }
+        self.multi_v().get_function(self.mangled, n)
+    }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> ProjectPtr
+    for HybridSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    #[inline(always)]
+    unsafe fn project(&self, mask: usize) -> Self {
+        let mangled = PackedSieveTable::<Self, TRAIT_N, FUNC_N>::project_packed(self.mangled, mask);
+        Self::with_sieve(self.multi_v(), mangled)
+    }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> QueryPtr
+    for HybridSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    #[inline(always)]
+    fn query(&self, trait_id: TraitId) -> Option<Self> {
+        // The pointer was built from a reference to the table.
+        let table = unsafe { self.multi_v() };
+        let sieve = table.query_sieve(trait_id)?;
+        Some(Self::with_sieve(
+            table,
+            PackedSieveTable::<Self, TRAIT_N, FUNC_N>::with_packed_sieve(self.mangled, sieve),
+        ))
+    }
+}
+
+#[test]
+fn hybrid_sieve_project_test() {
+    fn returns<const N: u64>(_: *const (), _: u64) -> u64 {
+        N
+    }
+    fn call<P: FuncTablePtr<Arg = u64, Ret = u64>>(ptr: P, n: u32) -> u64 {
+        unsafe { ptr.get_function(n)(std::ptr::null(), 0) }
+    }
+
+    let table = PackedSieveTable::<_, 3, 2>::new_with_trait_ids(
+        &[
+            returns::<0>,
+            returns::<1>,
+            returns::<2>,
+            returns::<3>,
+            returns::<4>,
+            returns::<5>,
+        ],
+        [10, 11, 12],
+    );
+    let ptr = HybridSievePtr::new(&table);
+    for n in 0..6 {
+        assert!(call(ptr, n) == n as u64);
+    }
+    assert!(unsafe { ptr.get_primary_function(1)(std::ptr::null(), 0) } == 1);
+
+    let ptr = unsafe { ptr.project(0b110) };
+    assert!(unsafe { ptr.get_primary_function(0)(std::ptr::null(), 0) } == 2);
+    assert!(call(ptr, 1) == 3);
+    assert!(call(ptr, 2) == 4);
+
+    let ptr = ptr.query(12).unwrap();
+    assert!(unsafe { ptr.get_primary_function(1)(std::ptr::null(), 0) } == 5);
+    assert!(ptr.query(13).is_none());
+}
+
+#[test]
+fn hybrid_sieve_padded_trait_test() {
+    use crate::fibonacci::{fibonacci_trait_ids, make_fibonacci_packed_sieve};
+
+    // 8 functions for 5 traits of 2: the 5th trait is all fillers, but has a table.
+    let table = make_fibonacci_packed_sieve::<_, 5, 2>();
+    let ptr = HybridSievePtr::new(&table)
+        .query(fibonacci_trait_ids::<5>()[4])
+        .unwrap();
+    let result =
+        std::panic::catch_unwind(|| unsafe { ptr.get_primary_function(0)(std::ptr::null(), 0) });
+    assert!(result.is_err(), "the filler should panic");
+}
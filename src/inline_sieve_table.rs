@@ -1,17 +1,29 @@
-use crate::{filler_function, FuncTablePtr, Function};
+use std::mem::size_of;
 
+use crate::{
+    filler_function,
+    footprint::{count_fillers, Footprint, TableFootprint},
+    FuncTablePtr, Function, ProjectPtr, QueryPtr, TraitId,
+};
+
+/// `repr(C)` so that `funcs` is at the start of the table, where pointers point. The fields C can
+/// read come first, see [`crate::abi`].
+#[repr(C)]
 pub struct InlineSieveTable<Ptr: FuncTablePtr, const N: usize> {
-    funcs: [Function<Ptr>; N],
+    pub(crate) funcs: [Function<Ptr>; N],
     /// A default sieve pointer for sieves.
     ///
     /// The real SieveTable would not contain a sieve (only pointers). But this makes the simulation
     /// a bit more realistic. We the bit will be 0 where None was passed to `SieveTable::new()`.
-    default_sieve: usize,
+    pub(crate) default_sieve: usize,
+    /// The id of each trait, with the bits of its slots.
+    traits: Vec<(TraitId, usize)>,
 }
 
 impl<Ptr: FuncTablePtr, const N: usize> InlineSieveTable<Ptr, N> {
     pub fn new(opt_funcs: [Option<Function<Ptr>>; N]) -> Self {
-        let funcs = opt_funcs.map(|of| of.unwrap_or(filler_function));
+        assert!(N <= usize::BITS as usize, "More than {} slots", usize::BITS);
+        let funcs = opt_funcs.map(|of| of.unwrap_or(filler_function::<Ptr>));
         let mut default_sieve: usize = 0;
         for (n, f) in opt_funcs.iter().enumerate() {
             if f.is_some() {
@@ -21,20 +33,56 @@ impl<Ptr: FuncTablePtr, const N: usize> InlineSieveTable<Ptr, N> {
         Self {
             funcs,
             default_sieve,
+            traits: Vec::new(),
+        }
+    }
+
+    /// Like `new`, also naming the traits so that pointers can be queried for them.
+    ///
+    /// `traits` lists the id of each trait with a mask of its slots.
+    pub fn new_with_traits(
+        opt_funcs: [Option<Function<Ptr>>; N],
+        traits: &[(TraitId, usize)],
+    ) -> Self {
+        let table = Self {
+            traits: traits.to_vec(),
+            ..Self::new(opt_funcs)
+        };
+        for (id, sieve) in traits {
+            assert!(
+                sieve & !table.default_sieve == 0,
+                "trait {} has empty slots",
+                id
+            );
         }
+        table
     }
 }
 
+impl<Ptr: FuncTablePtr, const N: usize> TableFootprint for InlineSieveTable<Ptr, N> {
+    fn footprint(&self) -> Footprint {
+        Footprint {
+            pointer_bytes: size_of::<Ptr>(),
+            table_bytes: size_of::<Self>(),
+            heap_bytes: self.traits.capacity() * size_of::<(TraitId, usize)>(),
+            slots: N,
+            filler_slots: count_fillers::<Ptr>(&self.funcs),
+        }
+    }
+}
+
+#[repr(C)]
 pub struct InlineSievePtr<'vt, ArgT, RetT, const N: usize> {
-    sieve: usize,
-    funcs: &'vt [Function<Self>; N],
+    pub(crate) sieve: usize,
+    /// The whole table rather than its `funcs`, so that `query` may read its traits too.
+    pub(crate) table: &'vt InlineSieveTable<Self, N>,
 }
 
 impl<'vt, ArgT, RetT, const N: usize> InlineSievePtr<'vt, ArgT, RetT, N> {
     pub fn new(sieve_table: &'vt InlineSieveTable<Self, N>) -> Self {
         Self {
             sieve: sieve_table.default_sieve,
-            funcs: &sieve_table.funcs,
+            table: sieve_table,
         }
     }
 }
@@ -49,10 +97,34 @@ impl<'vt, ArgT, RetT, const N: usize> Clone for InlineSievePtr<'vt, ArgT, RetT,
 impl<'vt, ArgT, RetT, const N: usize> FuncTablePtr for InlineSievePtr<'vt, ArgT, RetT, N> {
     type Arg = ArgT;
     type Ret = RetT;
+    type Markers = ();
     #[inline(always)]
     unsafe fn get_function(&self, n: u32) -> Function<Self> {
         let offset = crate::select_bit::find_nth_set_bit(self.sieve, n);
-        let ptr = self.funcs.get_unchecked(offset as usize);
+        let ptr = self.table.funcs.get_unchecked(offset as usize);
         *ptr
     }
 }
+
+/// Projects over functions rather than traits: the inline sieve has one bit per slot and does not
+/// know how slots are grouped into traits. To hide a trait, clear the bits of all its functions.
+impl<'vt, ArgT, RetT, const N: usize> ProjectPtr for InlineSievePtr<'vt, ArgT, RetT, N> {
+    #[inline(always)]
+    unsafe fn project(&self, mask: usize) -> Self {
+        Self {
+            sieve: crate::select_bit::deposit_bits(mask, self.sieve),
+            table: self.table,
+        }
+    }
+}
+
+impl<'vt, ArgT, RetT, const N: usize> QueryPtr for InlineSievePtr<'vt, ArgT, RetT, N> {
+    #[inline(always)]
+    fn query(&self, trait_id: TraitId) -> Option<Self> {
+        let (_, sieve) = self.table.traits.iter().find(|(id, _)| *id == trait_id)?;
+        Some(Self {
+            sieve: *sieve,
+            table: self.table,
+        })
+    }
+}
@@ -0,0 +1,489 @@
+//! A tree-walking interpreter over arithmetic expressions.
+//!
+//! Every node of the tree stores its table pointer, the way a `Box<dyn Eval + Display + Visit>`
+//! would, so the size of the pointer representation affects the size of the tree.
+//!
+//! The functions in the tables receive a pointer to the node as their data, and recurse into the
+//! children through the children's own table pointers. The traits are:
+//!
+//! * `Eval`: `eval` (and a filler).
+//! * `Display`: `width`, the length of the expression once printed, and `precedence`.
+//! * `Visit`: `count_nodes` and `depth`.
+use crate::{
+    inline_sieve_table::InlineSieveTable, multi_ptrs::MultiVTable,
+    packed_sieve_table::PackedSieveTable, v_table::VTable, FuncTablePtr, Function,
+};
+
+/// Number of traits a node implements.
+pub const TRAIT_N: usize = 3;
+/// Number of methods per trait.
+pub const FUNC_N: usize = 2;
+
+const EVAL: u32 = 0;
+const WIDTH: u32 = 2;
+const PRECEDENCE: u32 = 3;
+const COUNT_NODES: u32 = 4;
+const DEPTH: u32 = 5;
+
+/// The kinds of nodes, each of which has its own table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Literal,
+    Add,
+    Sub,
+    Mul,
+    Neg,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 5] = [Kind::Literal, Kind::Add, Kind::Sub, Kind::Mul, Kind::Neg];
+}
+
+/// A node of the expression tree.
+///
+/// The constructors are `unsafe`: the methods call the functions of `meta` on the node, without
+/// checking the table they come from.
+pub struct Node<P> {
+    meta: P,
+    value: i64,
+    lhs: Option<Box<Node<P>>>,
+    rhs: Option<Box<Node<P>>>,
+}
+
+impl<P> Node<P>
+where
+    P: FuncTablePtr<Arg = (), Ret = i64>,
+{
+    /// # Safety
+    ///
+    /// Behavior is undefined unless `meta` points to the table `make_interpreter_*` returns for
+    /// the kind of the node: the methods call its functions up to the `TRAIT_N * FUNC_N`-th, which
+    /// expect a `Node<P>` of that kind as their data.
+    pub unsafe fn literal(meta: P, value: i64) -> Box<Self> {
+        Box::new(Self {
+            meta,
+            value,
+            lhs: None,
+            rhs: None,
+        })
+    }
+
+    /// # Safety
+    ///
+    /// As `literal`.
+    pub unsafe fn unary(meta: P, operand: Box<Self>) -> Box<Self> {
+        Box::new(Self {
+            meta,
+            value: 0,
+            lhs: Some(operand),
+            rhs: None,
+        })
+    }
+
+    /// # Safety
+    ///
+    /// As `literal`.
+    pub unsafe fn binary(meta: P, lhs: Box<Self>, rhs: Box<Self>) -> Box<Self> {
+        Box::new(Self {
+            meta,
+            value: 0,
+            lhs: Some(lhs),
+            rhs: Some(rhs),
+        })
+    }
+
+    #[inline(always)]
+    fn call(&self, n: u32) -> i64 {
+        // The constructors require a table with all the methods, for nodes.
+        let func = unsafe { self.meta.get_function(n) };
+        func(self as *const Self as *const (), ())
+    }
+
+    pub fn eval(&self) -> i64 {
+        self.call(EVAL)
+    }
+
+    pub fn width(&self) -> i64 {
+        self.call(WIDTH)
+    }
+
+    pub fn precedence(&self) -> i64 {
+        self.call(PRECEDENCE)
+    }
+
+    pub fn count_nodes(&self) -> i64 {
+        self.call(COUNT_NODES)
+    }
+
+    pub fn depth(&self) -> i64 {
+        self.call(DEPTH)
+    }
+}
+
+mod funcptrs {
+    use super::Node;
+    use crate::FuncTablePtr;
+
+    /// Returns the node the functions of the table of `I` receive.
+    ///
+    /// # Safety
+    ///
+    /// Behavior is undefined unless `data` points to a `Node<I>`.
+    #[inline(always)]
+    unsafe fn node<'a, I>(data: *const ()) -> &'a Node<I> {
+        &*(data as *const Node<I>)
+    }
+
+    /// # Safety
+    ///
+    /// Behavior is undefined unless `data` points to a `Node<I>`.
+    #[inline(always)]
+    unsafe fn lhs<'a, I>(data: *const ()) -> &'a Node<I> {
+        node::<I>(data).lhs.as_deref().expect("missing lhs")
+    }
+
+    /// # Safety
+    ///
+    /// Behavior is undefined unless `data` points to a `Node<I>`.
+    #[inline(always)]
+    unsafe fn rhs<'a, I>(data: *const ()) -> &'a Node<I> {
+        node::<I>(data).rhs.as_deref().expect("missing rhs")
+    }
+
+    /// Returns the width of `child`, with the parentheses it needs under an operator of
+    /// precedence `min`.
+    fn child_width<I>(child: &Node<I>, min: i64) -> i64
+    where
+        I: FuncTablePtr<Arg = (), Ret = i64>,
+    {
+        if child.precedence() < min {
+            child.width() + 2
+        } else {
+            child.width()
+        }
+    }
+
+    declare_filler!(eval_01, (), i64);
+
+    pub fn literal_eval<I>(data: *const (), _: ()) -> i64
+    where
+        I: FuncTablePtr<Arg = (), Ret = i64>,
+    {
+        unsafe { node::<I>(data) }.value
+    }
+
+    pub fn literal_width<I>(data: *const (), _: ()) -> i64
+    where
+        I: FuncTablePtr<Arg = (), Ret = i64>,
+    {
+        let value = unsafe { node::<I>(data) }.value;
+        let mut width = if value < 0 { 2 } else { 1 };
+        let mut rest = value.unsigned_abs() / 10;
+        while rest != 0 {
+            width += 1;
+            rest /= 10;
+        }
+        width
+    }
+
+    pub fn leaf_count_nodes(_data: *const (), _: ()) -> i64 {
+        1
+    }
+
+    pub fn leaf_depth(_data: *const (), _: ()) -> i64 {
+        1
+    }
+
+    pub fn add_eval<I>(data: *const (), _: ()) -> i64
+    where
+        I: FuncTablePtr<Arg = (), Ret = i64>,
+    {
+        let (lhs, rhs) = unsafe { (lhs::<I>(data), rhs::<I>(data)) };
+        lhs.eval().wrapping_add(rhs.eval())
+    }
+
+    pub fn sub_eval<I>(data: *const (), _: ()) -> i64
+    where
+        I: FuncTablePtr<Arg = (), Ret = i64>,
+    {
+        let (lhs, rhs) = unsafe { (lhs::<I>(data), rhs::<I>(data)) };
+        lhs.eval().wrapping_sub(rhs.eval())
+    }
+
+    pub fn mul_eval<I>(data: *const (), _: ()) -> i64
+    where
+        I: FuncTablePtr<Arg = (), Ret = i64>,
+    {
+        let (lhs, rhs) = unsafe { (lhs::<I>(data), rhs::<I>(data)) };
+        lhs.eval().wrapping_mul(rhs.eval())
+    }
+
+    pub fn neg_eval<I>(data: *const (), _: ()) -> i64
+    where
+        I: FuncTablePtr<Arg = (), Ret = i64>,
+    {
+        unsafe { lhs::<I>(data) }.eval().wrapping_neg()
+    }
+
+    /// Returns `PREC`, the precedence of the node.
+    pub fn precedence<const PREC: i64>(_data: *const (), _: ()) -> i64 {
+        PREC
+    }
+
+    /// The width of a left-associative infix operator of precedence `PREC`, e.g. `a + b`.
+    pub fn binary_width<I, const PREC: i64>(data: *const (), _: ()) -> i64
+    where
+        I: FuncTablePtr<Arg = (), Ret = i64>,
+    {
+        let (lhs, rhs) = unsafe { (lhs::<I>(data), rhs::<I>(data)) };
+        child_width(lhs, PREC) + 3 + child_width(rhs, PREC + 1)
+    }
+
+    pub fn binary_count_nodes<I>(data: *const (), _: ()) -> i64
+    where
+        I: FuncTablePtr<Arg = (), Ret = i64>,
+    {
+        let (lhs, rhs) = unsafe { (lhs::<I>(data), rhs::<I>(data)) };
+        1 + lhs.count_nodes() + rhs.count_nodes()
+    }
+
+    pub fn binary_depth<I>(data: *const (), _: ()) -> i64
+    where
+        I: FuncTablePtr<Arg = (), Ret = i64>,
+    {
+        let (lhs, rhs) = unsafe { (lhs::<I>(data), rhs::<I>(data)) };
+        1 + lhs.depth().max(rhs.depth())
+    }
+
+    /// The width of a prefix operator of precedence `PREC`, e.g. `-a`.
+    pub fn unary_width<I, const PREC: i64>(data: *const (), _: ()) -> i64
+    where
+        I: FuncTablePtr<Arg = (), Ret = i64>,
+    {
+        1 + child_width(unsafe { lhs::<I>(data) }, PREC)
+    }
+
+    pub fn unary_count_nodes<I>(data: *const (), _: ()) -> i64
+    where
+        I: FuncTablePtr<Arg = (), Ret = i64>,
+    {
+        1 + unsafe { lhs::<I>(data) }.count_nodes()
+    }
+
+    pub fn unary_depth<I>(data: *const (), _: ()) -> i64
+    where
+        I: FuncTablePtr<Arg = (), Ret = i64>,
+    {
+        1 + unsafe { lhs::<I>(data) }.depth()
+    }
+}
+
+/// Returns the methods of the nodes of kind `kind`, trait after trait.
+fn funcs<I>(kind: Kind) -> [Function<I>; TRAIT_N * FUNC_N]
+where
+    I: FuncTablePtr<Arg = (), Ret = i64>,
+{
+    use funcptrs::*;
+    match kind {
+        Kind::Literal => [
+            literal_eval::<I>,
+            eval_01,
+            literal_width::<I>,
+            precedence::<4>,
+            leaf_count_nodes,
+            leaf_depth,
+        ],
+        Kind::Add => [
+            add_eval::<I>,
+            eval_01,
+            binary_width::<I, 1>,
+            precedence::<1>,
+            binary_count_nodes::<I>,
+            binary_depth::<I>,
+        ],
+        Kind::Sub => [
+            sub_eval::<I>,
+            eval_01,
+            binary_width::<I, 1>,
+            precedence::<1>,
+            binary_count_nodes::<I>,
+            binary_depth::<I>,
+        ],
+        Kind::Mul => [
+            mul_eval::<I>,
+            eval_01,
+            binary_width::<I, 2>,
+            precedence::<2>,
+            binary_count_nodes::<I>,
+            binary_depth::<I>,
+        ],
+        Kind::Neg => [
+            neg_eval::<I>,
+            eval_01,
+            unary_width::<I, 3>,
+            precedence::<3>,
+            unary_count_nodes::<I>,
+            unary_depth::<I>,
+        ],
+    }
+}
+
+/// Returns a random tree where every path from the root to a leaf has `depth` nodes.
+///
+/// `metas` holds the table pointer for every kind, in the order of `Kind::ALL`.
+///
+/// # Safety
+///
+/// Behavior is undefined unless each of `metas` satisfies `Node::literal` for its kind.
+pub unsafe fn random_tree<P>(metas: &[P; 5], depth: u32, seed: u64) -> Box<Node<P>>
+where
+    P: FuncTablePtr<Arg = (), Ret = i64>,
+{
+    unsafe fn build<P>(metas: &[P; 5], depth: u32, state: &mut u64) -> Box<Node<P>>
+    where
+        P: FuncTablePtr<Arg = (), Ret = i64>,
+    {
+        let random = crate::random::next(state);
+        if depth <= 1 {
+            return Node::literal(metas[0], (random % 10) as i64);
+        }
+        // Skips `Kind::Literal`.
+        let kind = Kind::ALL[1 + (random % 4) as usize];
+        let meta = metas[kind as usize];
+        match kind {
+            Kind::Neg => Node::unary(meta, build(metas, depth - 1, state)),
+            _ => Node::binary(
+                meta,
+                build(metas, depth - 1, state),
+                build(metas, depth - 1, state),
+            ),
+        }
+    }
+    build(metas, depth, &mut crate::random::state_from_seed(seed))
+}
+
+/// Calls a method of every trait on the root.
+pub fn interpret<P>(root: &Node<P>) -> i64
+where
+    P: FuncTablePtr<Arg = (), Ret = i64>,
+{
+    root.eval()
+        .wrapping_add(root.width())
+        .wrapping_add(root.count_nodes())
+}
+
+/// A single trait holding the methods of all traits.
+pub fn make_interpreter_vtable<I>(kind: Kind) -> VTable<I, { TRAIT_N * FUNC_N }>
+where
+    I: FuncTablePtr<Arg = (), Ret = i64>,
+{
+    VTable::new(funcs::<I>(kind))
+}
+
+pub fn make_interpreter_multiptr<I>(kind: Kind) -> MultiVTable<I, TRAIT_N, FUNC_N>
+where
+    I: FuncTablePtr<Arg = (), Ret = i64>,
+{
+    MultiVTable::new(&funcs::<I>(kind))
+}
+
+pub fn make_interpreter_packed_sieve<I>(kind: Kind) -> PackedSieveTable<I, TRAIT_N, FUNC_N>
+where
+    I: FuncTablePtr<Arg = (), Ret = i64>,
+{
+    PackedSieveTable::new(&funcs::<I>(kind))
+}
+
+/// Interleaves the methods with holes, as if the table had traits the pointer cannot see.
+pub fn make_interpreter_inline_sieve<I>(kind: Kind) -> InlineSieveTable<I, 8>
+where
+    I: FuncTablePtr<Arg = (), Ret = i64>,
+{
+    let [f0, f1, f2, f3, f4, f5] = funcs::<I>(kind);
+    InlineSieveTable::new([
+        Some(f0),
+        Some(f1),
+        None,
+        Some(f2),
+        Some(f3),
+        None,
+        Some(f4),
+        Some(f5),
+    ])
+}
+
+/// Checks the methods on `(1 + 2) * -3`, then checks that `random_tree` agrees with `expected`.
+///
+/// `metas` must point to the tables `make_interpreter_*` returns, as for `random_tree`.
+#[cfg(test)]
+fn check_interpreter<P>(metas: &[P; 5], expected: i64)
+where
+    P: FuncTablePtr<Arg = (), Ret = i64>,
+{
+    let [literal, add, _, mul, neg] = *metas;
+    let tree = unsafe {
+        let sum = Node::binary(add, Node::literal(literal, 1), Node::literal(literal, 2));
+        Node::binary(mul, sum, Node::unary(neg, Node::literal(literal, 3)))
+    };
+    assert!(tree.eval() == -9);
+    assert!(tree.width() == "(1 + 2) * -3".len() as i64);
+    assert!(tree.count_nodes() == 6);
+    assert!(tree.depth() == 3);
+
+    let tree = unsafe { random_tree(metas, 8, 42) };
+    assert!(tree.depth() == 8);
+    assert!(interpret(&*tree) == expected);
+}
+
+#[cfg(test)]
+fn interpret_expected() -> i64 {
+    use crate::v_table::VPtr;
+
+    let tables = Kind::ALL.map(make_interpreter_vtable);
+    let metas = [0, 1, 2, 3, 4].map(|k| VPtr::new(&tables[k]));
+    interpret(&*unsafe { random_tree(&metas, 8, 42) })
+}
+
+#[test]
+fn interpreter_vptr_test() {
+    use crate::v_table::VPtr;
+
+    let tables = Kind::ALL.map(make_interpreter_vtable);
+    check_interpreter(
+        &[0, 1, 2, 3, 4].map(|k| VPtr::new(&tables[k])),
+        interpret_expected(),
+    );
+}
+
+#[test]
+fn interpreter_multiptr_test() {
+    use crate::multi_ptrs::MultiVPtr;
+
+    let tables = Kind::ALL.map(make_interpreter_multiptr);
+    check_interpreter(
+        &[0, 1, 2, 3, 4].map(|k| MultiVPtr::new(&tables[k])),
+        interpret_expected(),
+    );
+}
+
+#[test]
+fn interpreter_packed_sieve_test() {
+    use crate::packed_sieve_table::PackedSievePtr;
+
+    let tables = Kind::ALL.map(make_interpreter_packed_sieve);
+    check_interpreter(
+        &[0, 1, 2, 3, 4].map(|k| PackedSievePtr::new(&tables[k])),
+        interpret_expected(),
+    );
+}
+
+#[test]
+fn interpreter_inline_sieve_test() {
+    use crate::inline_sieve_table::InlineSievePtr;
+
+    let tables = Kind::ALL.map(make_interpreter_inline_sieve);
+    check_interpreter(
+        &[0, 1, 2, 3, 4].map(|k| InlineSievePtr::new(&tables[k])),
+        interpret_expected(),
+    );
+}
@@ -0,0 +1,204 @@
+//! Interface tables resolved lazily through a `(type, trait) → table` cache, as Go does.
+//!
+//! Types register their methods and traits the indices of the methods they need. The first call
+//! through a `(type, trait)` pair builds its table, an "itab" in Go's terms, and caches it in an
+//! open-addressing hash table. Later calls find it there.
+//!
+//! Go looks up the cache when converting to an interface, and then calls through the itab.
+//! `ItabPtr` looks it up on every call, which is what the sum of several traits would need
+//! without a table for the sum.
+use std::{
+    cell::{Cell, RefCell},
+    mem::size_of,
+    ptr::null,
+};
+
+use crate::{
+    footprint::{Footprint, TableFootprint},
+    v_table::VTable,
+    FuncTable, FuncTablePtr, Function,
+};
+
+/// The key of an empty slot. No type has `u32::MAX` traits.
+const EMPTY: u64 = u64::MAX;
+
+/// `(type_id << 32 | trait_id, itab)`, `EMPTY` if unused.
+type Slot<Ptr, const FUNC_N: usize> = Cell<(u64, *const VTable<Ptr, FUNC_N>)>;
+
+pub struct ItabCache<Ptr: FuncTablePtr, const FUNC_N: usize> {
+    /// The methods of each type, indexed by type id.
+    types: Vec<Vec<Function<Ptr>>>,
+    /// The indices of the methods of each trait, indexed by trait id.
+    traits: Vec<Vec<u32>>,
+    /// The length is a power of 2, at least twice the number of pairs, so lookups always end.
+    slots: Box<[Slot<Ptr, FUNC_N>]>,
+    /// Owns the tables `slots` points to.
+    itabs: RefCell<Vec<Box<VTable<Ptr, FUNC_N>>>>,
+}
+
+impl<Ptr: FuncTablePtr, const FUNC_N: usize> ItabCache<Ptr, FUNC_N> {
+    pub fn new() -> Self {
+        Self {
+            types: Vec::new(),
+            traits: Vec::new(),
+            slots: Self::empty_slots(1),
+            itabs: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Registers a type with its methods, and returns its id.
+    pub fn add_type(&mut self, methods: &[Function<Ptr>]) -> u32 {
+        self.types.push(methods.to_vec());
+        self.grow();
+        (self.types.len() - 1) as u32
+    }
+
+    /// Registers a trait with the indices of its methods in the types, and returns its id.
+    pub fn add_trait(&mut self, methods: &[u32]) -> u32 {
+        assert!(
+            methods.len() <= FUNC_N,
+            "trait has {} methods, maximum is {}",
+            methods.len(),
+            FUNC_N
+        );
+        self.traits.push(methods.to_vec());
+        self.grow();
+        (self.traits.len() - 1) as u32
+    }
+
+    fn empty_slots(len: usize) -> Box<[Slot<Ptr, FUNC_N>]> {
+        (0..len).map(|_| Cell::new((EMPTY, null()))).collect()
+    }
+
+    /// Makes room for every `(type, trait)` pair, keeping the cached tables.
+    fn grow(&mut self) {
+        let len = (2 * self.types.len() * self.traits.len()).next_power_of_two();
+        if len <= self.slots.len() {
+            return;
+        }
+        let old = std::mem::replace(&mut self.slots, Self::empty_slots(len));
+        for slot in old.iter() {
+            let (key, itab) = slot.get();
+            if key != EMPTY {
+                let index = self.probe(key);
+                self.slots[index].set((key, itab));
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn hash(key: u64) -> usize {
+        (key.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 32) as usize
+    }
+
+    /// Returns the index of the slot of `key`, or of the empty slot where it belongs.
+    #[inline(always)]
+    fn probe(&self, key: u64) -> usize {
+        let mask = self.slots.len() - 1;
+        let mut index = Self::hash(key) & mask;
+        loop {
+            let (k, _) = self.slots[index].get();
+            if k == key || k == EMPTY {
+                return index;
+            }
+            index = (index + 1) & mask;
+        }
+    }
+
+    /// Returns the table of `trait_id` for `type_id`, building it on the first call.
+    ///
+    /// # Safety
+    ///
+    /// Behavior is undefined if `type_id` or `trait_id` were not registered.
+    #[inline(always)]
+    pub unsafe fn itab(&self, type_id: u32, trait_id: u32) -> &VTable<Ptr, FUNC_N> {
+        let key = ((type_id as u64) << 32) | trait_id as u64;
+        let index = self.probe(key);
+        let (k, itab) = self.slots.get_unchecked(index).get();
+        if k == key {
+            &*itab
+        } else {
+            self.resolve(type_id, trait_id, index)
+        }
+    }
+
+    /// Builds the table of `trait_id` for `type_id` and caches it in `slots[index]`.
+    #[cold]
+    #[inline(never)]
+    fn resolve(&self, type_id: u32, trait_id: u32, index: usize) -> &VTable<Ptr, FUNC_N> {
+        let methods = &self.types[type_id as usize];
+        let funcs: Vec<Function<Ptr>> = self.traits[trait_id as usize]
+            .iter()
+            .map(|m| methods[*m as usize])
+            .collect();
+        let itab = Box::new(VTable::new_from_slice(&funcs));
+        let ptr: *const VTable<Ptr, FUNC_N> = &*itab;
+        self.itabs.borrow_mut().push(itab);
+        let key = ((type_id as u64) << 32) | trait_id as u64;
+        self.slots[index].set((key, ptr));
+        // The box is never dropped before `self`, and moving it doesn't move the table.
+        unsafe { &*ptr }
+    }
+}
+
+impl<Ptr: FuncTablePtr, const FUNC_N: usize> Default for ItabCache<Ptr, FUNC_N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Ptr: FuncTablePtr, const FUNC_N: usize> TableFootprint for ItabCache<Ptr, FUNC_N> {
+    /// Only counts the tables resolved so far, not the method lists they are built from.
+    fn footprint(&self) -> Footprint {
+        let itabs = self.itabs.borrow();
+        Footprint {
+            pointer_bytes: size_of::<Ptr>(),
+            table_bytes: size_of::<Self>(),
+            heap_bytes: self.slots.len() * size_of::<Slot<Ptr, FUNC_N>>()
+                + itabs.len() * size_of::<VTable<Ptr, FUNC_N>>(),
+            slots: itabs.len() * FUNC_N,
+            filler_slots: itabs.iter().map(|t| t.footprint().filler_slots).sum(),
+        }
+    }
+}
+
+/// Points to a type registered in an `ItabCache`, seeing every trait of the cache in id order.
+///
+/// The `n`-th function is the `n % FUNC_N`-th method of the trait with id `n / FUNC_N`.
+#[repr(C)]
+pub struct ItabPtr<'vt, ArgT, RetT, const FUNC_N: usize> {
+    pub(crate) cache: &'vt ItabCache<Self, FUNC_N>,
+    pub(crate) type_id: u32,
+}
+
+impl<'vt, ArgT, RetT, const FUNC_N: usize> ItabPtr<'vt, ArgT, RetT, FUNC_N> {
+    pub fn new(cache: &'vt ItabCache<Self, FUNC_N>, type_id: u32) -> Self {
+        assert!(
+            (type_id as usize) < cache.types.len(),
+            "unknown type {}",
+            type_id
+        );
+        Self { cache, type_id }
+    }
+}
+
+impl<'vt, ArgT, RetT, const FUNC_N: usize> Copy for ItabPtr<'vt, ArgT, RetT, FUNC_N> {}
+impl<'vt, ArgT, RetT, const FUNC_N: usize> Clone for ItabPtr<'vt, ArgT, RetT, FUNC_N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'vt, ArgT, RetT, const FUNC_N: usize> FuncTablePtr for ItabPtr<'vt, ArgT, RetT, FUNC_N> {
+    type Arg = ArgT;
+    type Ret = RetT;
+    type Markers = ();
+    #[inline(always)]
+    unsafe fn get_function(&self, n: u32) -> Function<Self> {
+        let trait_id = n / (FUNC_N as u32);
+        let trait_func_n = n % (FUNC_N as u32);
+        self.cache
+            .itab(self.type_id, trait_id)
+            .get_function(trait_func_n)
+    }
+}
@@ -1,18 +1,62 @@
 /// The V-Ptr or Sieve-Ptr or Extra-Fat-Ptr we are benching.
 ///
-/// For simplicity all functions have the same type.
+/// For simplicity all functions have the same type: they take the data pointer of the object, like
+/// `&self`, and an argument. To call another method of the object, pair the data pointer with the
+/// table pointer again.
 pub trait FuncTablePtr: Copy {
     type Arg;
     type Ret;
+    /// The marker traits the objects paired with the pointer implement, `()` if none (see
+    /// [`markers`]).
+    type Markers: ?Sized + markers::MarkerSet;
     /// Returns the `n`-th function from the pointer.
     ///
     /// # Safety
     ///
     /// Behavior is undefined if the pointer knows about fewer than `n` functions.
-    unsafe fn get_function(&self, n: u32) -> fn(Self, Self::Arg) -> Self::Ret;
+    unsafe fn get_function(&self, n: u32) -> Function<Self>;
 }
 
-pub trait FuncTable: Copy {
+/// A pointer that can hide some of its traits without allocating.
+///
+/// This is the `Box<A+B+C>` → `Box<A+C>` conversion the sieve representations are about.
+pub trait ProjectPtr: FuncTablePtr {
+    /// Returns a pointer that only sees the currently visible traits whose rank is set in `mask`.
+    ///
+    /// For instance if the pointer sees traits A, B and C, `0b101` returns a pointer that sees A
+    /// and C, in that order.
+    ///
+    /// # Safety
+    ///
+    /// Behavior is undefined if `mask` has bits set beyond the number of visible traits.
+    unsafe fn project(&self, mask: usize) -> Self;
+}
+
+/// Identifies a trait, across all tables.
+pub type TraitId = u32;
+
+/// Stands for an unknown trait in the trait ids of a table.
+pub const NO_TRAIT_ID: TraitId = TraitId::MAX;
+
+/// A pointer that knows which traits its concrete type implements.
+///
+/// The sieve pointers implement it, their tables naming the traits. The others don't, on purpose:
+/// - `VPtr`, `RelVPtr`, `MultiVPtr`, `CppPtr` and `DisplacementPtr` have no trait ids in their
+///   tables, and no way to reach the tables of the traits they don't see.
+/// - `TraitSearchPtr` searches the ids when it is built, with `TraitSearchPtr::new`, and only
+///   keeps the index of each table so that calls don't go through the ids.
+/// - `ItabPtr` and `PerfectHashPtr` always see every trait of their type: there is no pointer to
+///   a single trait to return.
+pub trait QueryPtr: FuncTablePtr {
+    /// Returns a pointer that only sees the trait `trait_id`, or `None` if the concrete type does
+    /// not implement it.
+    ///
+    /// The trait does not need to be visible from `self`: this also casts between sibling traits,
+    /// e.g. from `Box<A+B>` to `Box<C>` when the concrete type implements A, B and C.
+    fn query(&self, trait_id: TraitId) -> Option<Self>;
+}
+
+pub trait FuncTable {
     type Ptr: FuncTablePtr;
     /// Returns the `n`-th function from the table.
     ///
@@ -23,12 +67,12 @@ pub trait FuncTable: Copy {
 }
 
 // A function for a given pointer type.
-type Function<P> = fn(P, <P as FuncTablePtr>::Arg) -> <P as FuncTablePtr>::Ret;
+type Function<P> = fn(*const (), <P as FuncTablePtr>::Arg) -> <P as FuncTablePtr>::Ret;
 
 /// Panics when called.
 ///
 /// Used a placeholder when a `Function` is needed.
-pub fn filler_function<I: FuncTablePtr>(_: I, _: I::Arg) -> I::Ret {
+pub fn filler_function<I: FuncTablePtr>(_: *const (), _: I::Arg) -> I::Ret {
     panic!("filler function should never be called");
 }
 
@@ -38,10 +82,7 @@ pub fn filler_function<I: FuncTablePtr>(_: I, _: I::Arg) -> I::Ret {
 macro_rules! declare_filler {
     ($name:ident, $arg:ty, $ret:ty) => {
         /// A filler function that panics.
-        pub fn $name<P>(_: P, _: P::Arg) -> P::Ret
-        where
-            P: $crate::FuncTablePtr<Arg = $arg, Ret = $ret>,
-        {
+        pub fn $name(_: *const (), _: $arg) -> $ret {
             panic!(
                 "filler function `{}` should not be called",
                 stringify!($name)
@@ -50,9 +91,45 @@ macro_rules! declare_filler {
     };
 }
 
+/// Generates the tables of an ordinary trait, e.g. `#[table_trait(id = 1)] trait Arith { … }`.
+///
+/// The methods take `&self` and have the same argument and return types, the arguments being
+/// passed as a tuple if there are several. Next to the trait, the attribute generates:
+///
+/// - `ArithMarker` (or `marker = "…"`), the `typed::TraitMarker` of the trait with the given
+///   `TraitId`. Its constants are the slots of the methods, e.g. `ArithMarker::ADD`.
+/// - `typed::TraitFunctions` for every type implementing the trait, from which the constructors
+///   of `typed` build the tables of each representation.
+/// - The trait for a `SumBox` of a `Typed` pointer, `boxed.add(1)` calling the right function.
+///   It relies on `SumBox::new`, which is unsafe, pairing the value with tables of its type.
+///
+/// See `tests/table_trait.rs`.
+pub use bench_vtables_macros::table_trait;
+
+pub mod abi;
+pub mod collection;
+pub mod convert;
+pub mod cpp_table;
+pub mod displacement;
 pub mod fibonacci;
+pub mod footprint;
+pub mod hybrid_sieve;
 pub mod inline_sieve_table;
+pub mod interpreter;
+pub mod itab;
+pub mod markers;
+pub mod megamorphic;
 pub mod multi_ptrs;
 pub mod packed_sieve_table;
+pub mod perfect_hash;
+pub mod provider;
+mod random;
+pub mod rel_packed_sieve_table;
+pub mod rel_v_table;
 mod select_bit;
+pub mod sieve_table;
+pub mod split_sieve;
+pub mod sum_box;
+pub mod trait_search;
+pub mod typed;
 pub mod v_table;
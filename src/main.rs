@@ -1,9 +1,410 @@
-//! Runs a fibonacci computation. Exists for the purpose of a being disassembled.
-use bench_vtables::fibonacci;
+//! Runs a workload with a given representation, then prints the result and how long it took.
+//!
+//! Every workload has an `#[inline(never)]` entry point per representation, e.g.
+//! `fibonacci_packed_sieve` or `collection_multi_vptr`, so that the dispatch sequences are easy to
+//! find with `objdump -d --demangle` or `cargo asm` without editing the source.
+//!
+//! Usage: `bench_vtables [--repr REPR] [--traits N] [--workload WORKLOAD] [--size N] [--repeat N]`
+//!
+//! * `REPR` is one of `vptr`, `multi-vptr`, `packed-sieve` (default) or `inline-sieve`.
+//! * `--traits` is the number of traits for `fibonacci` (default 1 for `vptr`, 3 otherwise). The
+//!   methods are spread as densely as possible over the traits. The other workloads have a fixed
+//!   number of traits.
+//! * `WORKLOAD` is one of `fibonacci` (default), `collection`, `megamorphic` or `interpreter`.
+//! * `--size` is `n` for `fibonacci` (default 20), the number of objects for `collection`, the
+//!   number of tables for `megamorphic` and the depth of the tree for `interpreter`, at most
+//!   `MAX_TREE_DEPTH`.
+//! * `--repeat` is how many times to run the workload, at least once (default 1).
+use std::{
+    env,
+    hint::black_box,
+    process,
+    time::{Duration, Instant},
+};
+
+use bench_vtables::{
+    collection::{self, KIND_N},
+    fibonacci::{self, fibonacci},
+    inline_sieve_table::InlineSievePtr,
+    interpreter::{self, Kind, Node},
+    megamorphic,
+    multi_ptrs::MultiVPtr,
+    packed_sieve_table::PackedSievePtr,
+    sum_box::SumBox,
+    v_table::VPtr,
+};
+
+#[derive(Clone, Copy, Debug)]
+enum Representation {
+    VPtr,
+    MultiVPtr,
+    PackedSieve,
+    InlineSieve,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Workload {
+    Fibonacci,
+    Collection,
+    Megamorphic,
+    Interpreter,
+}
+
+/// The deepest tree of the `interpreter` workload: the number of nodes doubles with every level.
+const MAX_TREE_DEPTH: u64 = 24;
+
+struct Args {
+    repr: Representation,
+    /// `None` for the default of the representation.
+    traits: Option<usize>,
+    workload: Workload,
+    size: u64,
+    repeat: u32,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: bench_vtables [--repr vptr|multi-vptr|packed-sieve|inline-sieve] [--traits N] \
+         [--workload fibonacci|collection|megamorphic|interpreter] [--size N] [--repeat N]"
+    );
+    process::exit(2);
+}
+
+fn parse_args() -> Args {
+    let mut args = Args {
+        repr: Representation::PackedSieve,
+        traits: None,
+        workload: Workload::Fibonacci,
+        size: 20,
+        repeat: 1,
+    };
+    let mut argv = env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        let value = argv.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--repr" => {
+                args.repr = match value.as_str() {
+                    "vptr" => Representation::VPtr,
+                    "multi-vptr" => Representation::MultiVPtr,
+                    "packed-sieve" => Representation::PackedSieve,
+                    "inline-sieve" => Representation::InlineSieve,
+                    _ => usage(),
+                }
+            }
+            "--workload" => {
+                args.workload = match value.as_str() {
+                    "fibonacci" => Workload::Fibonacci,
+                    "collection" => Workload::Collection,
+                    "megamorphic" => Workload::Megamorphic,
+                    "interpreter" => Workload::Interpreter,
+                    _ => usage(),
+                }
+            }
+            "--traits" => args.traits = Some(value.parse().unwrap_or_else(|_| usage())),
+            "--size" => args.size = value.parse().unwrap_or_else(|_| usage()),
+            "--repeat" => {
+                args.repeat = value.parse().unwrap_or_else(|_| usage());
+                if args.repeat == 0 {
+                    usage();
+                }
+            }
+            _ => usage(),
+        }
+    }
+    args
+}
+
+/// Runs `f` `repeat` times, returns the last result and the total time.
+fn timed<R>(repeat: u32, mut f: impl FnMut() -> R) -> (R, Duration) {
+    let start = Instant::now();
+    let mut result = black_box(f());
+    for _ in 1..repeat {
+        result = black_box(f());
+    }
+    (result, start.elapsed())
+}
+
+#[inline(never)]
+fn fibonacci_vptr<const N: usize>(ptr: VPtr<u64, u64, N>, n: u64) -> u64 {
+    fibonacci(ptr, n)
+}
+
+#[inline(never)]
+fn fibonacci_multi_vptr<const TRAIT_N: usize, const FUNC_N: usize>(
+    ptr: MultiVPtr<u64, u64, TRAIT_N, FUNC_N>,
+    n: u64,
+) -> u64 {
+    fibonacci(ptr, n)
+}
+
+#[inline(never)]
+fn fibonacci_packed_sieve<const TRAIT_N: usize, const FUNC_N: usize>(
+    ptr: PackedSievePtr<u64, u64, TRAIT_N, FUNC_N>,
+    n: u64,
+) -> u64 {
+    fibonacci(ptr, n)
+}
+
+#[inline(never)]
+fn fibonacci_inline_sieve<const N: usize>(ptr: InlineSievePtr<u64, u64, N>, n: u64) -> u64 {
+    fibonacci(ptr, n)
+}
+
+fn run_fibonacci(args: &Args) -> Result<(u64, Duration), String> {
+    let n = args.size;
+    let traits = args.traits.unwrap_or(match args.repr {
+        Representation::VPtr => 1,
+        _ => 3,
+    });
+    let repeat = args.repeat;
+    // Maps the number of traits to the constants, with `FUNC_N` the smallest number of methods per
+    // trait that fits the 8 fibonacci functions.
+    macro_rules! by_traits {
+        ($make:ident, $($trait_n:literal => $func_n:literal),*) => {
+            match traits {
+                $($trait_n => $make!($trait_n, $func_n),)*
+                traits => Err(format!("{:?} does not support {} traits", args.repr, traits)),
+            }
+        };
+    }
+    macro_rules! multi_vptr {
+        ($trait_n:literal, $func_n:literal) => {{
+            let table = fibonacci::make_fibonacci_multiptr::<_, $trait_n, $func_n>();
+            let ptr = MultiVPtr::new(&table);
+            Ok(timed(repeat, || fibonacci_multi_vptr(black_box(ptr), n)))
+        }};
+    }
+    macro_rules! packed_sieve {
+        ($trait_n:literal, $func_n:literal) => {{
+            let table = fibonacci::make_fibonacci_packed_sieve::<_, $trait_n, $func_n>();
+            let ptr = PackedSievePtr::new(&table);
+            Ok(timed(repeat, || fibonacci_packed_sieve(black_box(ptr), n)))
+        }};
+    }
+    macro_rules! inline_sieve {
+        ($trait_n:literal, $func_n:literal) => {{
+            let table = fibonacci::make_fibonacci_inline_sieve_sized::<
+                _,
+                $trait_n,
+                $func_n,
+                { $trait_n * ($func_n + 1) },
+            >();
+            let ptr = InlineSievePtr::new(&table);
+            Ok(timed(repeat, || fibonacci_inline_sieve(black_box(ptr), n)))
+        }};
+    }
+    match args.repr {
+        Representation::VPtr if traits == 1 => {
+            let table = fibonacci::make_fibonacci_vtable();
+            let ptr = VPtr::new(&table);
+            Ok(timed(repeat, || fibonacci_vptr(black_box(ptr), n)))
+        }
+        Representation::VPtr => Err("VPtr only supports 1 trait".to_string()),
+        Representation::MultiVPtr => by_traits!(multi_vptr,
+            1 => 8, 2 => 4, 3 => 3, 4 => 2, 5 => 2, 6 => 2, 7 => 2, 8 => 1, 12 => 1, 16 => 1),
+        Representation::PackedSieve => by_traits!(packed_sieve,
+            1 => 8, 2 => 4, 3 => 3, 4 => 2, 5 => 2, 6 => 2, 7 => 2, 8 => 1),
+        Representation::InlineSieve => by_traits!(inline_sieve,
+            1 => 8, 2 => 4, 3 => 3, 4 => 2, 5 => 2, 6 => 2, 7 => 2, 8 => 1, 12 => 1, 16 => 1),
+    }
+}
+
+#[inline(never)]
+unsafe fn collection_vptr(objects: &[SumBox<VPtr<u64, u64, 6>>], arg: u64) -> u64 {
+    collection::sum_methods(objects, arg)
+}
+
+#[inline(never)]
+unsafe fn collection_multi_vptr(objects: &[SumBox<MultiVPtr<u64, u64, 3, 2>>], arg: u64) -> u64 {
+    collection::sum_methods(objects, arg)
+}
+
+#[inline(never)]
+unsafe fn collection_packed_sieve(
+    objects: &[SumBox<PackedSievePtr<u64, u64, 3, 2>>],
+    arg: u64,
+) -> u64 {
+    collection::sum_methods(objects, arg)
+}
+
+#[inline(never)]
+unsafe fn collection_inline_sieve(
+    objects: &[SumBox<InlineSievePtr<u64, u64, 8>>],
+    arg: u64,
+) -> u64 {
+    collection::sum_methods(objects, arg)
+}
+
+fn run_collection(args: &Args) -> (u64, Duration) {
+    let kinds = collection::shuffled_kinds(args.size as usize, 42);
+    macro_rules! run {
+        ($make:path, $ptr:ident, $entry:ident) => {{
+            let tables: Vec<_> = (0..KIND_N).map($make).collect();
+            let metas: Vec<_> = tables.iter().map($ptr::new).collect();
+            // The pointers are those of the tables `$make` returns for each kind.
+            let objects = unsafe { collection::make_objects(&kinds, &metas) };
+            // The objects come from `make_objects`.
+            timed(args.repeat, || unsafe { $entry(black_box(&objects), 3) })
+        }};
+    }
+    match args.repr {
+        Representation::VPtr => run!(collection::make_collection_vtable, VPtr, collection_vptr),
+        Representation::MultiVPtr => run!(
+            collection::make_collection_multiptr,
+            MultiVPtr,
+            collection_multi_vptr
+        ),
+        Representation::PackedSieve => run!(
+            collection::make_collection_packed_sieve,
+            PackedSievePtr,
+            collection_packed_sieve
+        ),
+        Representation::InlineSieve => run!(
+            collection::make_collection_inline_sieve,
+            InlineSievePtr,
+            collection_inline_sieve
+        ),
+    }
+}
+
+#[inline(never)]
+unsafe fn megamorphic_vptr(objects: &[VPtr<u64, u64, 6>], arg: u64) -> u64 {
+    megamorphic::dispatch_all(objects, arg)
+}
+
+#[inline(never)]
+unsafe fn megamorphic_multi_vptr(objects: &[MultiVPtr<u64, u64, 3, 2>], arg: u64) -> u64 {
+    megamorphic::dispatch_all(objects, arg)
+}
+
+#[inline(never)]
+unsafe fn megamorphic_packed_sieve(objects: &[PackedSievePtr<u64, u64, 3, 2>], arg: u64) -> u64 {
+    megamorphic::dispatch_all(objects, arg)
+}
+
+#[inline(never)]
+unsafe fn megamorphic_inline_sieve(objects: &[InlineSievePtr<u64, u64, 9>], arg: u64) -> u64 {
+    megamorphic::dispatch_all(objects, arg)
+}
+
+fn run_megamorphic(args: &Args) -> Result<(u64, Duration), String> {
+    let table_n = args.size as usize;
+    if table_n == 0 || table_n > megamorphic::TABLE_N {
+        return Err(format!(
+            "the number of tables must be between 1 and {}",
+            megamorphic::TABLE_N
+        ));
+    }
+    let ids = megamorphic::shuffled_tables(table_n, 4096, 42);
+    macro_rules! run {
+        ($make:path, $ptr:ident, $entry:ident) => {{
+            let tables: Vec<_> = (0..table_n).map($make).collect();
+            let objects: Vec<_> = ids.iter().map(|id| $ptr::new(&tables[*id])).collect();
+            // The objects point to the tables of `$make`.
+            Ok(timed(args.repeat, || unsafe {
+                $entry(black_box(&objects), 3)
+            }))
+        }};
+    }
+    match args.repr {
+        Representation::VPtr => run!(megamorphic::make_megamorphic_vtable, VPtr, megamorphic_vptr),
+        Representation::MultiVPtr => run!(
+            megamorphic::make_megamorphic_multiptr,
+            MultiVPtr,
+            megamorphic_multi_vptr
+        ),
+        Representation::PackedSieve => run!(
+            megamorphic::make_megamorphic_packed_sieve,
+            PackedSievePtr,
+            megamorphic_packed_sieve
+        ),
+        Representation::InlineSieve => run!(
+            megamorphic::make_megamorphic_inline_sieve,
+            InlineSievePtr,
+            megamorphic_inline_sieve
+        ),
+    }
+}
+
+#[inline(never)]
+fn interpreter_vptr(root: &Node<VPtr<(), i64, 6>>) -> i64 {
+    interpreter::interpret(root)
+}
+
+#[inline(never)]
+fn interpreter_multi_vptr(root: &Node<MultiVPtr<(), i64, 3, 2>>) -> i64 {
+    interpreter::interpret(root)
+}
+
+#[inline(never)]
+fn interpreter_packed_sieve(root: &Node<PackedSievePtr<(), i64, 3, 2>>) -> i64 {
+    interpreter::interpret(root)
+}
+
+#[inline(never)]
+fn interpreter_inline_sieve(root: &Node<InlineSievePtr<(), i64, 8>>) -> i64 {
+    interpreter::interpret(root)
+}
+
+fn run_interpreter(args: &Args) -> Result<(i64, Duration), String> {
+    if args.size == 0 || args.size > MAX_TREE_DEPTH {
+        return Err(format!(
+            "the depth of the tree must be between 1 and {}",
+            MAX_TREE_DEPTH
+        ));
+    }
+    macro_rules! run {
+        ($make:path, $ptr:ident, $entry:ident) => {{
+            let tables = Kind::ALL.map($make);
+            let metas = [0, 1, 2, 3, 4].map(|k| $ptr::new(&tables[k]));
+            // `metas` point to the tables of `$make`, in the order of `Kind::ALL`.
+            let tree = unsafe { interpreter::random_tree(&metas, args.size as u32, 42) };
+            Ok(timed(args.repeat, || $entry(black_box(&*tree))))
+        }};
+    }
+    match args.repr {
+        Representation::VPtr => run!(interpreter::make_interpreter_vtable, VPtr, interpreter_vptr),
+        Representation::MultiVPtr => run!(
+            interpreter::make_interpreter_multiptr,
+            MultiVPtr,
+            interpreter_multi_vptr
+        ),
+        Representation::PackedSieve => run!(
+            interpreter::make_interpreter_packed_sieve,
+            PackedSievePtr,
+            interpreter_packed_sieve
+        ),
+        Representation::InlineSieve => run!(
+            interpreter::make_interpreter_inline_sieve,
+            InlineSievePtr,
+            interpreter_inline_sieve
+        ),
+    }
+}
 
 fn main() {
-    let table = fibonacci::make_fibonacci_packed_sieve::<_, 3, 4>();
-    let ptr = bench_vtables::packed_sieve_table::PackedSievePtr::new(&table);
-    let fib = fibonacci::fibonacci(ptr, 20);
-    println!("fib={}", fib);
+    let args = parse_args();
+    let outcome = match args.workload {
+        Workload::Fibonacci => run_fibonacci(&args).map(|(r, d)| (r.to_string(), d)),
+        Workload::Collection => Ok(run_collection(&args)).map(|(r, d)| (r.to_string(), d)),
+        Workload::Megamorphic => run_megamorphic(&args).map(|(r, d)| (r.to_string(), d)),
+        Workload::Interpreter => run_interpreter(&args).map(|(r, d)| (r.to_string(), d)),
+    };
+    match outcome {
+        Ok((result, duration)) => {
+            println!(
+                "{:?} {:?} size={} result={} time={:?} ({:?} per run)",
+                args.workload,
+                args.repr,
+                args.size,
+                result,
+                duration,
+                duration / args.repeat
+            );
+        }
+        Err(error) => {
+            eprintln!("error: {}", error);
+            process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,161 @@
+//! Marker traits in sums of traits, such as `Box<dyn A + B + Send>`.
+//!
+//! Marker traits have no methods, so they have no slots in the tables and no bits in the sieves.
+//! `Marked` carries them in the type of the pointer instead, as a `dyn` of the markers: a
+//! `Marked<PackedSievePtr<…>, dyn Send + Sync>` is the same word as the `PackedSievePtr`, and
+//! its masks for `ProjectPtr::project` only count the traits with methods. Dropping markers, as in
+//! `Box<dyn A + Send>` → `Box<dyn A>`, is a conversion between types that changes no bit.
+//!
+//! The markers are checked when a value is paired with the pointer: `SumBox::new` requires the
+//! value to implement the markers of the pointer, and a `SumBox` is `Send` or `Sync` when they
+//! include these traits.
+use std::marker::PhantomData;
+
+use crate::{FuncTablePtr, Function, ProjectPtr, QueryPtr, TraitId};
+
+/// A set of marker traits: `()` for none, `dyn Send`, `dyn Sync` or `dyn Send + Sync`.
+pub trait MarkerSet {}
+
+impl MarkerSet for () {}
+impl MarkerSet for dyn Send {}
+impl MarkerSet for dyn Sync {}
+impl MarkerSet for dyn Send + Sync {}
+
+/// Implemented by the types that implement every marker trait of `M`.
+///
+/// # Safety
+///
+/// Sending or sharing a `SumBox` relies on it.
+pub unsafe trait HasMarkers<M: ?Sized + MarkerSet> {}
+
+unsafe impl<T: ?Sized> HasMarkers<()> for T {}
+unsafe impl<T: ?Sized + Send> HasMarkers<dyn Send> for T {}
+unsafe impl<T: ?Sized + Sync> HasMarkers<dyn Sync> for T {}
+unsafe impl<T: ?Sized + Send + Sync> HasMarkers<dyn Send + Sync> for T {}
+
+/// Implemented by the sets of markers included in `M`, including `M` itself.
+pub trait SubsetOf<M: ?Sized + MarkerSet>: MarkerSet {}
+
+impl<M: ?Sized + MarkerSet> SubsetOf<M> for () {}
+impl SubsetOf<dyn Send> for dyn Send {}
+impl SubsetOf<dyn Send + Sync> for dyn Send {}
+impl SubsetOf<dyn Sync> for dyn Sync {}
+impl SubsetOf<dyn Send + Sync> for dyn Sync {}
+impl SubsetOf<dyn Send + Sync> for dyn Send + Sync {}
+
+/// A table pointer whose objects implement the marker traits `M`.
+#[repr(transparent)]
+pub struct Marked<P, M: ?Sized + MarkerSet> {
+    ptr: P,
+    markers: PhantomData<fn() -> *const M>,
+}
+
+impl<P: FuncTablePtr, M: ?Sized + MarkerSet> Marked<P, M> {
+    pub fn new(ptr: P) -> Self {
+        Self {
+            ptr,
+            markers: PhantomData,
+        }
+    }
+
+    /// Returns the pointer without the markers.
+    #[inline(always)]
+    pub fn ptr(self) -> P {
+        self.ptr
+    }
+
+    /// Drops some of the markers, e.g. `dyn Send + Sync` → `dyn Send`.
+    #[inline(always)]
+    pub fn forget<N>(self) -> Marked<P, N>
+    where
+        N: ?Sized + SubsetOf<M>,
+    {
+        Marked::new(self.ptr)
+    }
+}
+
+impl<P: Copy, M: ?Sized + MarkerSet> Copy for Marked<P, M> {}
+impl<P: Copy, M: ?Sized + MarkerSet> Clone for Marked<P, M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P: FuncTablePtr, M: ?Sized + MarkerSet> FuncTablePtr for Marked<P, M> {
+    type Arg = P::Arg;
+    type Ret = P::Ret;
+    type Markers = M;
+    #[inline(always)]
+    unsafe fn get_function(&self, n: u32) -> Function<Self> {
+        self.ptr.get_function(n)
+    }
+}
+
+/// The markers stay: they are not part of the mask.
+impl<P: ProjectPtr, M: ?Sized + MarkerSet> ProjectPtr for Marked<P, M> {
+    #[inline(always)]
+    unsafe fn project(&self, mask: usize) -> Self {
+        Self::new(self.ptr.project(mask))
+    }
+}
+
+impl<P: QueryPtr, M: ?Sized + MarkerSet> QueryPtr for Marked<P, M> {
+    #[inline(always)]
+    fn query(&self, trait_id: TraitId) -> Option<Self> {
+        self.ptr.query(trait_id).map(Self::new)
+    }
+}
+
+#[cfg(test)]
+fn returns<const N: u64>(_: *const (), _: u64) -> u64 {
+    N
+}
+
+#[test]
+fn marked_packed_sieve_project_test() {
+    use std::mem::size_of;
+
+    use crate::packed_sieve_table::{PackedSievePtr, PackedSieveTable};
+
+    let table = PackedSieveTable::<_, 3, 2>::new(&[
+        returns::<0>,
+        returns::<1>,
+        returns::<2>,
+        returns::<3>,
+        returns::<4>,
+        returns::<5>,
+    ]);
+    let ptr = Marked::<_, dyn Send + Sync>::new(PackedSievePtr::new(&table));
+    assert!(
+        size_of::<Marked<PackedSievePtr<u64, u64, 3, 2>, dyn Send + Sync>>()
+            == size_of::<PackedSievePtr<u64, u64, 3, 2>>()
+    );
+    let ptr = unsafe { ptr.project(0b110) };
+    assert!(unsafe { ptr.get_function(0)(std::ptr::null(), 0) } == 2);
+    assert!(unsafe { ptr.get_function(3)(std::ptr::null(), 0) } == 5);
+    let ptr: Marked<_, dyn Sync> = unsafe { ptr.project(0b10) }.forget();
+    assert!(unsafe { ptr.get_function(0)(std::ptr::null(), 0) } == 4);
+    let ptr: Marked<_, ()> = ptr.forget();
+    assert!(unsafe { ptr.ptr().get_function(1)(std::ptr::null(), 0) } == 5);
+}
+
+#[test]
+fn marked_inline_sieve_query_test() {
+    use crate::inline_sieve_table::{InlineSievePtr, InlineSieveTable};
+
+    let table = InlineSieveTable::new_with_traits(
+        [
+            Some(returns::<0>),
+            None,
+            Some(returns::<1>),
+            Some(returns::<2>),
+        ],
+        &[(3, 0b0001), (7, 0b1100)],
+    );
+    let ptr = Marked::<_, dyn Send>::new(InlineSievePtr::new(&table));
+    let ptr = unsafe { ptr.project(0b110) };
+    assert!(unsafe { ptr.get_function(0)(std::ptr::null(), 0) } == 1);
+    let first = ptr.query(3).expect("the type implements trait 3");
+    assert!(unsafe { first.get_function(0)(std::ptr::null(), 0) } == 0);
+    assert!(ptr.query(5).is_none());
+}
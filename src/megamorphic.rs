@@ -0,0 +1,223 @@
+//! Dispatches at random among many distinct tables from the same call site.
+//!
+//! `fibonacci` and `collection` use a handful of tables that stay in L1. Here there are up to
+//! `TABLE_N` tables, each with its own function, so the memory footprint of the tables (e.g. the
+//! 256 bytes alignment of `PackedSieveTable`) and of the pointers shows in the results.
+//!
+//! Every table has `TRAIT_N` traits of `FUNC_N` methods, and the call site calls the first method
+//! of the last trait, which is where the sieves have the most bits to skip.
+use crate::{
+    displacement::DisplacementTable, inline_sieve_table::InlineSieveTable, multi_ptrs::MultiVTable,
+    packed_sieve_table::PackedSieveTable, v_table::VTable, FuncTablePtr, Function,
+};
+
+/// Maximum number of distinct tables.
+pub const TABLE_N: usize = 1024;
+/// Number of traits in each table.
+pub const TRAIT_N: usize = 3;
+/// Number of methods per trait.
+pub const FUNC_N: usize = 2;
+/// The index of the method the call site calls.
+const CALLED: u32 = ((TRAIT_N - 1) * FUNC_N) as u32;
+
+mod funcptrs {
+    use crate::{FuncTablePtr, Function};
+
+    /// The method of table `HI * 32 + LO`.
+    pub fn method<const HI: u64, const LO: u64>(_data: *const (), arg: u64) -> u64 {
+        arg.wrapping_add(HI * 32 + LO)
+    }
+
+    macro_rules! methods {
+        ($hi:ident, $($lo:literal)*) => {
+            [$(method::<$hi, $lo>,)*]
+        };
+    }
+
+    fn methods_by_lo<I, const HI: u64>() -> [Function<I>; 32]
+    where
+        I: FuncTablePtr<Arg = u64, Ret = u64>,
+    {
+        methods!(HI, 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31)
+    }
+
+    /// Returns the method of table `id`, distinct from the method of every other table.
+    pub fn method_of<I>(id: usize) -> Function<I>
+    where
+        I: FuncTablePtr<Arg = u64, Ret = u64>,
+    {
+        macro_rules! by_hi {
+            ($($hi:literal)*) => {
+                match id / 32 {
+                    $($hi => methods_by_lo::<I, $hi>()[id % 32],)*
+                    _ => panic!("table {} is not below {}", id, super::TABLE_N),
+                }
+            };
+        }
+        by_hi!(0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31)
+    }
+}
+
+/// Returns the functions of table `id`: the method of the table in every slot.
+fn funcs<I>(id: usize) -> [Function<I>; TRAIT_N * FUNC_N]
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    [funcptrs::method_of::<I>(id); TRAIT_N * FUNC_N]
+}
+
+/// Returns `call_n` indices below `table_n`, each index appearing about as often, in a random order.
+pub fn shuffled_tables(table_n: usize, call_n: usize, seed: u64) -> Vec<usize> {
+    assert!(
+        table_n <= TABLE_N,
+        "{} tables, maximum is {}",
+        table_n,
+        TABLE_N
+    );
+    let mut ids: Vec<usize> = (0..call_n).map(|i| i % table_n).collect();
+    crate::random::shuffle(&mut ids, seed);
+    ids
+}
+
+/// Calls the method of every object, and returns the sum of the results.
+///
+/// # Safety
+///
+/// Behavior is undefined unless every object points to a table `make_megamorphic_*` returns.
+pub unsafe fn dispatch_all<I>(objects: &[I], arg: u64) -> u64
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    let mut sum = 0u64;
+    for object in objects {
+        let method = object.get_function(CALLED);
+        // The objects have no data, the methods don't read it.
+        sum = sum.wrapping_add(method(std::ptr::null(), arg));
+    }
+    sum
+}
+
+pub fn make_megamorphic_vtable<I>(id: usize) -> VTable<I, { TRAIT_N * FUNC_N }>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    VTable::new(funcs::<I>(id))
+}
+
+pub fn make_megamorphic_multiptr<I>(id: usize) -> MultiVTable<I, TRAIT_N, FUNC_N>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    MultiVTable::new(&funcs::<I>(id))
+}
+
+pub fn make_megamorphic_packed_sieve<I>(id: usize) -> PackedSieveTable<I, TRAIT_N, FUNC_N>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    PackedSieveTable::new(&funcs::<I>(id))
+}
+
+/// Puts a hole before every trait, as if the table had traits the pointer cannot see.
+pub fn make_megamorphic_inline_sieve<I>(id: usize) -> InlineSieveTable<I, 9>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    let f = Some(funcptrs::method_of::<I>(id));
+    InlineSieveTable::new([None, f, f, None, f, f, None, f, f])
+}
+
+/// Returns a single array with the rows of tables `0..table_n`, the row of table `id` having type
+/// id `id`.
+pub fn make_megamorphic_displacement<I>(table_n: usize) -> DisplacementTable<I>
+where
+    I: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    let mut table = DisplacementTable::new();
+    for id in 0..table_n {
+        table.add_type(&funcs::<I>(id).map(Some));
+    }
+    table
+}
+
+#[cfg(test)]
+fn dispatch_all_expected(ids: &[usize], arg: u64) -> u64 {
+    ids.iter()
+        .fold(0u64, |sum, id| sum.wrapping_add(arg + *id as u64))
+}
+
+#[test]
+fn megamorphic_distinct_functions_test() {
+    use crate::v_table::VPtr;
+
+    let mut methods: Vec<usize> = (0..TABLE_N)
+        .map(|id| funcptrs::method_of::<VPtr<u64, u64, 1>>(id) as usize)
+        .collect();
+    methods.sort_unstable();
+    methods.dedup();
+    assert!(methods.len() == TABLE_N);
+}
+
+#[test]
+fn megamorphic_vptr_test() {
+    use crate::v_table::VPtr;
+
+    let ids = shuffled_tables(TABLE_N, 4096, 42);
+    let tables: Vec<_> = (0..TABLE_N).map(make_megamorphic_vtable).collect();
+    let objects: Vec<_> = ids.iter().map(|id| VPtr::new(&tables[*id])).collect();
+    assert!(unsafe { dispatch_all(&objects, 3) } == dispatch_all_expected(&ids, 3));
+}
+
+#[test]
+fn megamorphic_multiptr_test() {
+    use crate::multi_ptrs::MultiVPtr;
+
+    let ids = shuffled_tables(TABLE_N, 4096, 42);
+    let tables: Vec<_> = (0..TABLE_N).map(make_megamorphic_multiptr).collect();
+    let objects: Vec<_> = ids.iter().map(|id| MultiVPtr::new(&tables[*id])).collect();
+    assert!(unsafe { dispatch_all(&objects, 3) } == dispatch_all_expected(&ids, 3));
+}
+
+#[test]
+fn megamorphic_packed_sieve_test() {
+    use crate::packed_sieve_table::PackedSievePtr;
+
+    let ids = shuffled_tables(TABLE_N, 4096, 42);
+    let tables: Vec<_> = (0..TABLE_N).map(make_megamorphic_packed_sieve).collect();
+    let objects: Vec<_> = ids
+        .iter()
+        .map(|id| PackedSievePtr::new(&tables[*id]))
+        .collect();
+    assert!(unsafe { dispatch_all(&objects, 3) } == dispatch_all_expected(&ids, 3));
+}
+
+#[test]
+fn megamorphic_inline_sieve_test() {
+    use crate::inline_sieve_table::InlineSievePtr;
+
+    let ids = shuffled_tables(TABLE_N, 4096, 42);
+    let tables: Vec<_> = (0..TABLE_N).map(make_megamorphic_inline_sieve).collect();
+    let objects: Vec<_> = ids
+        .iter()
+        .map(|id| InlineSievePtr::new(&tables[*id]))
+        .collect();
+    assert!(unsafe { dispatch_all(&objects, 3) } == dispatch_all_expected(&ids, 3));
+}
+
+#[test]
+fn megamorphic_displacement_test() {
+    use crate::{displacement::DisplacementPtr, ProjectPtr};
+
+    let ids = shuffled_tables(TABLE_N, 4096, 42);
+    let table = make_megamorphic_displacement(TABLE_N);
+    let objects: Vec<_> = ids
+        .iter()
+        .map(|id| DisplacementPtr::<_, _, FUNC_N>::new(&table, *id as u32))
+        .collect();
+    assert!(unsafe { dispatch_all(&objects, 3) } == dispatch_all_expected(&ids, 3));
+
+    // Hiding the first trait moves the called method to the previous trait.
+    let object = unsafe { objects[0].project(0b110) };
+    let method = unsafe { object.get_function(CALLED - FUNC_N as u32) };
+    assert!(method(std::ptr::null(), 3) == 3 + ids[0] as u64);
+}
@@ -1,8 +1,14 @@
-use crate::{FuncTable, FuncTablePtr, Function};
+use std::mem::size_of;
+
+use crate::{
+    footprint::{Footprint, TableFootprint},
+    FuncTable, FuncTablePtr, Function,
+};
 
 use crate::v_table::VTable;
+#[repr(C)]
 pub struct MultiVTable<Ptr: FuncTablePtr, const TRAIT_N: usize, const FUNC_N: usize> {
-    tables: [VTable<Ptr, FUNC_N>; TRAIT_N],
+    pub(crate) tables: [VTable<Ptr, FUNC_N>; TRAIT_N],
 }
 
 impl<Ptr: FuncTablePtr, const TRAIT_N: usize, const FUNC_N: usize>
@@ -15,6 +21,11 @@ impl<Ptr: FuncTablePtr, const TRAIT_N: usize, const FUNC_N: usize>
         }
         Self { tables }
     }
+    /// Returns the `trait_func_n`-th function of the `trait_n`-th trait.
+    ///
+    /// # Safety
+    ///
+    /// Behavior is undefined if the table has fewer than `trait_n` traits.
     #[inline(always)]
     pub unsafe fn get_trait_function(&self, trait_n: u32, trait_func_n: u32) -> Function<Ptr> {
         let trait_ptr = self.tables.get_unchecked(trait_n as usize);
@@ -22,8 +33,23 @@ impl<Ptr: FuncTablePtr, const TRAIT_N: usize, const FUNC_N: usize>
     }
 }
 
+impl<Ptr: FuncTablePtr, const TRAIT_N: usize, const FUNC_N: usize> TableFootprint
+    for MultiVTable<Ptr, TRAIT_N, FUNC_N>
+{
+    fn footprint(&self) -> Footprint {
+        Footprint {
+            pointer_bytes: size_of::<Ptr>(),
+            table_bytes: size_of::<Self>(),
+            heap_bytes: 0,
+            slots: TRAIT_N * FUNC_N,
+            filler_slots: self.tables.iter().map(|t| t.footprint().filler_slots).sum(),
+        }
+    }
+}
+
+#[repr(C)]
 pub struct MultiVPtr<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> {
-    ptrs: [&'vt VTable<Self, FUNC_N>; TRAIT_N],
+    pub(crate) ptrs: [&'vt VTable<Self, FUNC_N>; TRAIT_N],
 }
 
 impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize>
@@ -31,11 +57,16 @@ impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize>
 {
     pub fn new(mvt: &'vt MultiVTable<Self, TRAIT_N, FUNC_N>) -> Self {
         let mut tables_ptr = [&mvt.tables[0]; TRAIT_N];
-        for i in 1..TRAIT_N {
-            tables_ptr[i] = &mvt.tables[i]
+        for (ptr, table) in tables_ptr.iter_mut().zip(mvt.tables.iter()) {
+            *ptr = table;
         }
         Self { ptrs: tables_ptr }
     }
+    /// Returns the `trait_func_n`-th function of the `trait_n`-th trait.
+    ///
+    /// # Safety
+    ///
+    /// Behavior is undefined if the pointer has fewer than `trait_n` traits.
     #[inline(always)]
     pub unsafe fn get_trait_function(self, trait_n: u32, trait_func_n: u32) -> Function<Self> {
         let trait_ptr = self.ptrs.get_unchecked(trait_n as usize);
@@ -60,6 +91,7 @@ impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> FuncTablePtr
 {
     type Arg = ArgT;
     type Ret = RetT;
+    type Markers = ();
     #[inline(always)]
     unsafe fn get_function(&self, n: u32) -> Function<Self> {
         let trait_n = n / (FUNC_N as u32);
@@ -0,0 +1,132 @@
+//! Dispatch on global method ids ("selectors") through a per-type perfect hash.
+//!
+//! Each method of each trait has a selector, `selector(trait_n, trait_func_n)`. A type's table
+//! has `SLOTS` functions, and a multiplier chosen at construction so that
+//! `(selector * multiplier) >> (32 - log2(SLOTS))` sends each of its selectors to a distinct
+//! slot. Call sites know their selector, so a call is a multiplication, a shift and two loads
+//! whatever the number of traits, where a sieve needs to select among more bits.
+use std::mem::size_of;
+
+use crate::{
+    filler_function,
+    footprint::{count_fillers, Footprint, TableFootprint},
+    random, FuncTablePtr, Function,
+};
+
+/// How many multipliers to try before giving up.
+const MAX_TRIES: usize = 1 << 20;
+
+/// Returns the selector of the `trait_func_n`-th method of the `trait_n`-th trait.
+#[inline(always)]
+pub fn selector(trait_n: u32, trait_func_n: u32) -> u32 {
+    (trait_n << 16) | trait_func_n
+}
+
+pub struct PerfectHashTable<Ptr: FuncTablePtr, const SLOTS: usize> {
+    multiplier: u32,
+    funcs: [Function<Ptr>; SLOTS],
+}
+
+impl<Ptr: FuncTablePtr, const SLOTS: usize> PerfectHashTable<Ptr, SLOTS> {
+    const SHIFT: u32 = u32::BITS - SLOTS.trailing_zeros();
+
+    /// Builds a table for `methods`, a list of `(selector, function)`.
+    ///
+    /// Panics if `SLOTS` is not a power of 2 of at least 2, or if no multiplier is found. Each
+    /// multiplier works with a probability of about `exp(-methods² / (2 * SLOTS))`.
+    pub fn new(methods: &[(u32, Function<Ptr>)]) -> Self {
+        assert!(
+            SLOTS >= 2 && SLOTS.is_power_of_two(),
+            "SLOTS is {}, expected a power of 2",
+            SLOTS
+        );
+        assert!(methods.len() <= SLOTS, "more methods than slots");
+        let mut state = random::state_from_seed(SLOTS as u64);
+        for _ in 0..MAX_TRIES {
+            let multiplier = random::next(&mut state) as u32 | 1;
+            let mut used = [false; SLOTS];
+            let mut funcs = [filler_function::<Ptr> as Function<Ptr>; SLOTS];
+            let perfect = methods.iter().all(|(selector, func)| {
+                let slot = Self::slot(multiplier, *selector);
+                funcs[slot] = *func;
+                !std::mem::replace(&mut used[slot], true)
+            });
+            if perfect {
+                return Self { multiplier, funcs };
+            }
+        }
+        panic!(
+            "no perfect hash found for {} methods in {} slots",
+            methods.len(),
+            SLOTS
+        );
+    }
+
+    #[inline(always)]
+    fn slot(multiplier: u32, selector: u32) -> usize {
+        (selector.wrapping_mul(multiplier) >> Self::SHIFT) as usize
+    }
+
+    /// Returns the function of `selector`.
+    ///
+    /// # Safety
+    ///
+    /// Returns an arbitrary function of the table if `selector` was not passed to `new`.
+    #[inline(always)]
+    pub unsafe fn get_selector(&self, selector: u32) -> Function<Ptr> {
+        *self
+            .funcs
+            .get_unchecked(Self::slot(self.multiplier, selector))
+    }
+}
+
+impl<Ptr: FuncTablePtr, const SLOTS: usize> TableFootprint for PerfectHashTable<Ptr, SLOTS> {
+    fn footprint(&self) -> Footprint {
+        Footprint {
+            pointer_bytes: size_of::<Ptr>(),
+            table_bytes: size_of::<Self>(),
+            heap_bytes: 0,
+            slots: SLOTS,
+            filler_slots: count_fillers::<Ptr>(&self.funcs),
+        }
+    }
+}
+
+#[repr(C)]
+pub struct PerfectHashPtr<'vt, ArgT, RetT, const FUNC_N: usize, const SLOTS: usize> {
+    pub(crate) table: &'vt PerfectHashTable<Self, SLOTS>,
+}
+
+impl<'vt, ArgT, RetT, const FUNC_N: usize, const SLOTS: usize>
+    PerfectHashPtr<'vt, ArgT, RetT, FUNC_N, SLOTS>
+{
+    pub fn new(table: &'vt PerfectHashTable<Self, SLOTS>) -> Self {
+        Self { table }
+    }
+}
+
+impl<'vt, ArgT, RetT, const FUNC_N: usize, const SLOTS: usize> Copy
+    for PerfectHashPtr<'vt, ArgT, RetT, FUNC_N, SLOTS>
+{
+}
+impl<'vt, ArgT, RetT, const FUNC_N: usize, const SLOTS: usize> Clone
+    for PerfectHashPtr<'vt, ArgT, RetT, FUNC_N, SLOTS>
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'vt, ArgT, RetT, const FUNC_N: usize, const SLOTS: usize> FuncTablePtr
+    for PerfectHashPtr<'vt, ArgT, RetT, FUNC_N, SLOTS>
+{
+    type Arg = ArgT;
+    type Ret = RetT;
+    type Markers = ();
+    #[inline(always)]
+    unsafe fn get_function(&self, n: u32) -> Function<Self> {
+        let trait_n = n / (FUNC_N as u32);
+        let trait_func_n = n % (FUNC_N as u32);
+        self.table.get_selector(selector(trait_n, trait_func_n))
+    }
+}
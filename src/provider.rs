@@ -0,0 +1,43 @@
+//! The ABI shared by a host and the table providers it loads at runtime.
+//!
+//! A provider is a shared library, compiled separately from the host, that builds the tables of its
+//! own types. For each type it exports a `PackedSieveTable` and an `InlineSieveTable` of the traits
+//! `ARITH` and `BITS`, through the symbols below. The host only knows this module: it never links
+//! the functions in the tables. `table_provider/` is such a provider, loaded by
+//! `tests/table_provider.rs`.
+//!
+//! The tables end with fields only Rust can read and hold functions with the Rust ABI (see
+//! [`crate::abi`]), so the host and the providers must be built by the same compiler from the same
+//! version of this crate.
+use crate::{
+    inline_sieve_table::{InlineSievePtr, InlineSieveTable},
+    packed_sieve_table::{PackedSievePtr, PackedSieveTable},
+    TraitId,
+};
+
+pub const TRAIT_N: usize = 2;
+pub const FUNC_N: usize = 2;
+/// Each trait of the inline sieve table is followed by a slot the pointer cannot see.
+pub const INLINE_N: usize = TRAIT_N * (FUNC_N + 1);
+
+/// Two methods on a `u64`, e.g. add and multiply.
+pub const ARITH: TraitId = 1;
+/// Two methods on the bits of a `u64`, e.g. shift and count.
+pub const BITS: TraitId = 2;
+
+/// The tables live as long as the provider stays loaded.
+pub type PackedPtr = PackedSievePtr<'static, u64, u64, TRAIT_N, FUNC_N>;
+pub type PackedTable = PackedSieveTable<PackedPtr, TRAIT_N, FUNC_N>;
+pub type InlinePtr = InlineSievePtr<'static, u64, u64, INLINE_N>;
+pub type InlineTable = InlineSieveTable<InlinePtr, INLINE_N>;
+
+/// Returns the number of types of the provider.
+pub type TypeCountFn = extern "C" fn() -> u32;
+/// Returns the table of a type, or null if there is no such type.
+pub type PackedTableFn = extern "C" fn(type_id: u32) -> *const PackedTable;
+/// Returns the table of a type, or null if there is no such type.
+pub type InlineTableFn = extern "C" fn(type_id: u32) -> *const InlineTable;
+
+pub const TYPE_COUNT_SYMBOL: &[u8] = b"provider_type_count\0";
+pub const PACKED_TABLE_SYMBOL: &[u8] = b"provider_packed_table\0";
+pub const INLINE_TABLE_SYMBOL: &[u8] = b"provider_inline_table\0";
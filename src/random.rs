@@ -0,0 +1,26 @@
+//! A small deterministic random generator, good enough to defeat the branch predictor.
+
+/// Returns the next number of the xorshift64* sequence and advances `state`.
+///
+/// `state` must not be 0.
+pub(crate) fn next(state: &mut u64) -> u64 {
+    *state ^= *state >> 12;
+    *state ^= *state << 25;
+    *state ^= *state >> 27;
+    state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+}
+
+/// Returns a valid state for `next`, depending on `seed`.
+pub(crate) fn state_from_seed(seed: u64) -> u64 {
+    (seed ^ 0x9e37_79b9_7f4a_7c15).max(1)
+}
+
+/// Shuffles `items` in place. The order only depends on `seed`.
+pub(crate) fn shuffle<T>(items: &mut [T], seed: u64) {
+    // Fisher-Yates.
+    let mut state = state_from_seed(seed);
+    for i in (1..items.len()).rev() {
+        let random = next(&mut state);
+        items.swap(i, (random % (i as u64 + 1)) as usize);
+    }
+}
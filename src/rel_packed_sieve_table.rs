@@ -0,0 +1,94 @@
+//! Like `packed_sieve_table`, with `RelVTable`s instead of `VTable`s.
+use std::marker::PhantomData;
+
+use crate::{
+    rel_v_table::RelVTable,
+    sieve_table::{Align256, SieveTable},
+    FuncTablePtr, Function, ProjectPtr, QueryPtr, TraitId,
+};
+
+/// A `SieveTable` of `RelVTable`s, aligned as `PackedSieveTable`.
+pub type RelPackedSieveTable<Ptr, const TRAIT_N: usize, const FUNC_N: usize> =
+    SieveTable<RelVTable<Ptr, FUNC_N>, Align256, TRAIT_N>;
+
+#[repr(C)]
+pub struct RelPackedSievePtr<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> {
+    pub(crate) multi_v_mangled: usize, //  *const RelPackedSieveTable<Self, TRAIT_N, FUNC_N> | sieve
+    multi_v_phantom: PhantomData<&'vt RelPackedSieveTable<Self, TRAIT_N, FUNC_N>>,
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize>
+    RelPackedSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    pub fn new(sieve_table: &'vt RelPackedSieveTable<Self, TRAIT_N, FUNC_N>) -> Self {
+        Self::from_mangled(sieve_table.pack())
+    }
+
+    #[inline(always)]
+    fn from_mangled(multi_v_mangled: usize) -> Self {
+        Self {
+            multi_v_mangled,
+            multi_v_phantom: PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn multi_v(&self) -> &'vt RelPackedSieveTable<Self, TRAIT_N, FUNC_N> {
+        RelPackedSieveTable::unpack(self.multi_v_mangled)
+    }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> Copy
+    for RelPackedSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+}
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> Clone
+    for RelPackedSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> FuncTablePtr
+    for RelPackedSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    type Arg = ArgT;
+    type Ret = RetT;
+    type Markers = ();
+    #[inline(always)]
+    unsafe fn get_function(&self, n: u32) -> Function<Self> {
+        self.multi_v().get_function(self.multi_v_mangled, n)
+    }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> ProjectPtr
+    for RelPackedSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    #[inline(always)]
+    unsafe fn project(&self, mask: usize) -> Self {
+        Self::from_mangled(
+            RelPackedSieveTable::<Self, TRAIT_N, FUNC_N>::project_packed(
+                self.multi_v_mangled,
+                mask,
+            ),
+        )
+    }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> QueryPtr
+    for RelPackedSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    #[inline(always)]
+    fn query(&self, trait_id: TraitId) -> Option<Self> {
+        // The pointer was built from a reference to the table.
+        let sieve = unsafe { self.multi_v() }.query_sieve(trait_id)?;
+        Some(Self::from_mangled(RelPackedSieveTable::<
+            Self,
+            TRAIT_N,
+            FUNC_N,
+        >::with_packed_sieve(
+            self.multi_v_mangled, sieve
+        )))
+    }
+}
@@ -0,0 +1,204 @@
+//! Like `v_table`, storing 32-bit offsets instead of function pointers, as Swift does.
+//!
+//! Each offset is relative to the slot holding it: `get_function` adds the offset to the address
+//! of the slot. This only works if the table is within 2GiB of the functions and never moves, which
+//! Swift gets by emitting its tables next to the code. Tables of this crate are built at runtime,
+//! so they are written in place into `ARENA`, a static of the executable, and never freed.
+use std::{
+    borrow::Borrow,
+    cell::UnsafeCell,
+    convert::TryFrom,
+    marker::PhantomData,
+    mem::{align_of, size_of},
+    ops::Deref,
+    ptr::NonNull,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    filler_function,
+    footprint::{Footprint, TableFootprint},
+    FuncTable, FuncTablePtr, Function,
+};
+
+/// The number of bytes of `ARENA`.
+const ARENA_BYTES: usize = 1 << 20;
+
+/// The memory of the relative tables, in the executable and so within 2GiB of its functions.
+#[repr(align(16))]
+struct Arena {
+    bytes: UnsafeCell<[u8; ARENA_BYTES]>,
+    /// The number of bytes handed out.
+    used: AtomicUsize,
+}
+
+// `used` hands out disjoint bytes, each written once before being shared.
+unsafe impl Sync for Arena {}
+
+static ARENA: Arena = Arena {
+    bytes: UnsafeCell::new([0; ARENA_BYTES]),
+    used: AtomicUsize::new(0),
+};
+
+impl Arena {
+    /// Returns `size` bytes aligned to `align`, which must be at most 16.
+    ///
+    /// Panics if the arena is full.
+    fn alloc(&self, size: usize, align: usize) -> *mut u8 {
+        assert!(align <= align_of::<Self>(), "alignment of {} bytes", align);
+        let start = self
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                let start = (used + align - 1) & !(align - 1);
+                Some(start + size).filter(|end| *end <= ARENA_BYTES)
+            })
+            .map(|used| (used + align - 1) & !(align - 1))
+            .expect("the arena of relative tables is full");
+        unsafe { (self.bytes.get() as *mut u8).add(start) }
+    }
+}
+
+/// A table in `ARENA`, where it lives as long as the program.
+pub struct ArenaRef<T>(NonNull<T>);
+
+// Like `&'static T`.
+unsafe impl<T: Sync> Send for ArenaRef<T> {}
+unsafe impl<T: Sync> Sync for ArenaRef<T> {}
+
+impl<T> Deref for ArenaRef<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // `ARENA` is never freed, and its tables are never written once returned.
+        unsafe { self.0.as_ref() }
+    }
+}
+
+impl<T> Borrow<T> for ArenaRef<T> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+/// Returns the offset of `func` from `slot`.
+///
+/// Panics if `func` is more than 2GiB away from `slot`.
+pub(crate) fn encode<Ptr: FuncTablePtr>(func: Function<Ptr>, slot: *const i32) -> i32 {
+    let offset = (func as usize).wrapping_sub(slot as usize) as isize;
+    i32::try_from(offset).expect("function too far from its slot for a 32-bit offset")
+}
+
+/// Returns the function at the offset held by `slot`, from `slot`.
+///
+/// # Safety
+///
+/// `slot` must hold an offset from `encode::<Ptr>` with the same `slot`.
+#[inline(always)]
+pub(crate) unsafe fn decode<Ptr: FuncTablePtr>(slot: *const i32) -> Function<Ptr> {
+    let address = (slot as usize).wrapping_add(*slot as isize as usize);
+    std::mem::transmute::<usize, Function<Ptr>>(address)
+}
+
+/// Not `Copy` nor `Clone`: moving the table would break its offsets. The constructors return a
+/// table in `ARENA`, which lives as long as the program.
+#[repr(C)]
+pub struct RelVTable<Ptr: FuncTablePtr, const N: usize> {
+    offsets: [i32; N],
+    phantom: PhantomData<Function<Ptr>>,
+}
+
+impl<Ptr: FuncTablePtr, const N: usize> RelVTable<Ptr, N> {
+    pub fn new(funcs: [Function<Ptr>; N]) -> ArenaRef<Self> {
+        let table = ARENA.alloc(size_of::<Self>(), align_of::<Self>()) as *mut Self;
+        unsafe {
+            let offsets = std::ptr::addr_of_mut!((*table).offsets) as *mut i32;
+            for (n, func) in funcs.iter().enumerate() {
+                let slot = offsets.add(n);
+                slot.write(encode::<Ptr>(*func, slot));
+            }
+            ArenaRef(NonNull::new_unchecked(table))
+        }
+    }
+    pub fn new_with_filler() -> ArenaRef<Self> {
+        Self::new([filler_function::<Ptr>; N])
+    }
+    pub fn new_from_slice(funcs: &[Function<Ptr>]) -> ArenaRef<Self> {
+        assert!(
+            funcs.len() <= N,
+            "slice has size {}, maximum is {}",
+            funcs.len(),
+            N
+        );
+        let mut array: [Function<Ptr>; N] = [filler_function::<Ptr>; N];
+        array[..funcs.len()].copy_from_slice(funcs);
+        Self::new(array)
+    }
+}
+
+impl<PtrT: FuncTablePtr, const N: usize> FuncTable for RelVTable<PtrT, N> {
+    type Ptr = PtrT;
+    #[inline(always)]
+    unsafe fn get_function(&self, n: u32) -> Function<Self::Ptr> {
+        decode::<PtrT>(self.offsets.as_ptr().add(n as usize))
+    }
+}
+
+impl<Ptr: FuncTablePtr, const N: usize> TableFootprint for RelVTable<Ptr, N> {
+    fn footprint(&self) -> Footprint {
+        let filler = filler_function::<Ptr> as Function<Ptr> as usize;
+        let filler_slots = (0..N)
+            .filter(|n| unsafe { self.get_function(*n as u32) } as usize == filler)
+            .count();
+        Footprint {
+            pointer_bytes: size_of::<Ptr>(),
+            table_bytes: size_of::<Self>(),
+            heap_bytes: 0,
+            slots: N,
+            filler_slots,
+        }
+    }
+}
+
+#[repr(C)]
+pub struct RelVPtr<'vt, ArgT, RetT, const N: usize> {
+    pub(crate) table: &'vt RelVTable<Self, N>,
+}
+
+impl<'vt, ArgT, RetT, const N: usize> RelVPtr<'vt, ArgT, RetT, N> {
+    pub fn new(table: &'vt RelVTable<Self, N>) -> Self {
+        Self { table }
+    }
+}
+
+impl<'vt, ArgT, RetT, const N: usize> Copy for RelVPtr<'vt, ArgT, RetT, N> {}
+impl<'vt, ArgT, RetT, const N: usize> Clone for RelVPtr<'vt, ArgT, RetT, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'vt, ArgT, RetT, const N: usize> FuncTablePtr for RelVPtr<'vt, ArgT, RetT, N> {
+    type Arg = ArgT;
+    type Ret = RetT;
+    type Markers = ();
+    #[inline(always)]
+    unsafe fn get_function(&self, n: u32) -> Function<Self> {
+        self.table.get_function(n)
+    }
+}
+
+#[test]
+fn rel_v_table_slot_relative_test() {
+    fn returns<const N: u64>(_: *const (), _: u64) -> u64 {
+        N
+    }
+
+    let funcs: [fn(*const (), u64) -> u64; 3] = [returns::<0>, returns::<1>, returns::<2>];
+    let table = RelVTable::new(funcs);
+    for (n, func) in funcs.iter().enumerate() {
+        let slot = &table.offsets[n] as *const i32 as usize;
+        assert!(slot.wrapping_add(table.offsets[n] as isize as usize) == *func as usize);
+        assert!(
+            unsafe { RelVPtr::new(&table).get_function(n as u32)(std::ptr::null(), 0) } == n as u64
+        );
+    }
+}
@@ -74,3 +74,35 @@ pub unsafe fn find_nth_set_bit(sieve: usize, n: u32) -> u32 {
         // https://graphics.stanford.edu/~seander/bithacks.html#SelectPosFromMSBRank
     }
 }
+
+/// Returns the bits of `sieve` whose rank is set in `mask`.
+///
+/// In other words, the `n`-th set bit of `sieve` is kept if and only if the `n`-th bit of `mask`
+/// is set. This is what `pdep` does, hence the name.
+#[inline(always)]
+pub fn deposit_bits(mask: usize, sieve: usize) -> usize {
+    if cfg!(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        all(target_feature = "bmi1", target_feature = "bmi2"),
+        not(feature = "slow_pdep"),
+    )) {
+        if mem::size_of::<usize>() == 4 {
+            (mask as u32).pdep(sieve as u32) as usize
+        } else {
+            (mask as u64).pdep(sieve as u64) as usize
+        }
+    } else {
+        let mut result = 0;
+        let mut bits = sieve;
+        let mut rank = 0;
+        while bits != 0 {
+            let lowest = bits & bits.wrapping_neg();
+            if mask & (1 << rank) != 0 {
+                result |= lowest;
+            }
+            bits &= !lowest;
+            rank += 1;
+        }
+        result
+    }
+}
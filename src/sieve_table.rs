@@ -0,0 +1,235 @@
+//! The table behind the packed sieve pointers: a pointer to the table of each trait.
+//!
+//! `PackedSieveTable`, `RelPackedSieveTable` and `SplitSieveTable` are `SieveTable`s, differing in
+//! the table of each trait, `VTable` or `RelVTable`, and in the alignment of the table, which sets
+//! how many bits of its address the pointers can use for the sieve.
+use std::{
+    borrow::Borrow,
+    mem::{size_of, size_of_val},
+    ptr::null,
+};
+
+use crate::{
+    footprint::{Footprint, TableFootprint},
+    rel_v_table::{ArenaRef, RelVTable},
+    select_bit::{deposit_bits, find_nth_set_bit},
+    v_table::VTable,
+    FuncTable, FuncTablePtr, Function, TraitId, NO_TRAIT_ID,
+};
+
+/// The table of a trait in a `SieveTable`.
+pub trait TraitTable: FuncTable + TableFootprint {
+    /// The number of functions of the trait.
+    const FUNC_N: usize;
+    /// What the `SieveTable` keeps of each trait: the table, or a reference to it if it lives
+    /// elsewhere.
+    type Owned: Borrow<Self>;
+    /// The bytes of each trait outside of `Owned`.
+    const SEPARATE_BYTES: usize;
+    /// Returns the table of `funcs`, padded with fillers.
+    fn from_slice(funcs: &[Function<Self::Ptr>]) -> Self::Owned;
+}
+
+impl<Ptr: FuncTablePtr, const N: usize> TraitTable for VTable<Ptr, N> {
+    const FUNC_N: usize = N;
+    type Owned = Self;
+    const SEPARATE_BYTES: usize = 0;
+    fn from_slice(funcs: &[Function<Ptr>]) -> Self {
+        Self::new_from_slice(funcs)
+    }
+}
+
+/// The tables are in the arena of `rel_v_table`, since they must not move.
+impl<Ptr: FuncTablePtr, const N: usize> TraitTable for RelVTable<Ptr, N> {
+    const FUNC_N: usize = N;
+    type Owned = ArenaRef<Self>;
+    const SEPARATE_BYTES: usize = size_of::<Self>();
+    fn from_slice(funcs: &[Function<Ptr>]) -> ArenaRef<Self> {
+        Self::new_from_slice(funcs)
+    }
+}
+
+/// Aligns a `SieveTable` to 16 bytes, leaving 4 bits of its address for the sieve.
+#[repr(align(16))]
+pub struct Align16;
+
+/// Aligns a `SieveTable` to 256 bytes, leaving 8 bits of its address for the sieve.
+#[repr(align(256))]
+pub struct Align256;
+
+/// The fields C can read come first, see [`crate::abi`].
+///
+/// `T` is the table of each trait, and `A` gives the table its alignment, e.g. `Align256`.
+#[repr(C)]
+pub struct SieveTable<T: TraitTable, A, const TRAIT_N: usize> {
+    align: [A; 0],
+    pub(crate) traits_ptr: [*const T; TRAIT_N],
+    /// The id of each trait, or `NO_TRAIT_ID` if unknown.
+    pub(crate) trait_ids: [TraitId; TRAIT_N],
+    /// Owns the tables `traits_ptr` points to.
+    traits: Vec<T::Owned>,
+}
+
+impl<T: TraitTable, A, const TRAIT_N: usize> SieveTable<T, A, TRAIT_N> {
+    /// Splits `funcs` into `TRAIT_N` traits of `FUNC_N` functions, padding with fillers.
+    ///
+    /// Every trait gets a table, even past the end of `funcs`, so that any sieve finds one.
+    pub fn new(funcs: &[Function<T::Ptr>]) -> Self {
+        assert!(
+            funcs.len() <= TRAIT_N * T::FUNC_N,
+            "{} functions, maximum is {}",
+            funcs.len(),
+            TRAIT_N * T::FUNC_N
+        );
+        let mut chunks = funcs.chunks(T::FUNC_N);
+        let traits: Vec<T::Owned> = (0..TRAIT_N)
+            .map(|_| T::from_slice(chunks.next().unwrap_or(&[])))
+            .collect();
+        let mut traits_ptr = [null(); TRAIT_N];
+        for (i, vt) in traits.iter().enumerate() {
+            traits_ptr[i] = vt.borrow();
+        }
+        Self {
+            align: [],
+            traits_ptr,
+            trait_ids: [NO_TRAIT_ID; TRAIT_N],
+            traits,
+        }
+    }
+
+    /// Like `new`, also naming the traits so that pointers can be queried for them.
+    pub fn new_with_trait_ids(funcs: &[Function<T::Ptr>], trait_ids: [TraitId; TRAIT_N]) -> Self {
+        assert!(!trait_ids.contains(&NO_TRAIT_ID), "invalid trait id");
+        Self {
+            trait_ids,
+            ..Self::new(funcs)
+        }
+    }
+
+    /// Returns the `n`-th function of the traits `sieve` sees.
+    ///
+    /// Only the bits of the visible traits are read: `sieve` may carry other bits above them.
+    ///
+    /// # Safety
+    ///
+    /// Behavior is undefined if `sieve` sees fewer than `n` functions.
+    #[inline(always)]
+    pub(crate) unsafe fn get_function(&self, sieve: usize, n: u32) -> Function<T::Ptr> {
+        let trait_n = n / (T::FUNC_N as u32);
+        let trait_func_n = n % (T::FUNC_N as u32);
+        let trait_offset = find_nth_set_bit(sieve, trait_n);
+        let vt_ptr = *self.traits_ptr.get_unchecked(trait_offset as usize);
+        (*vt_ptr).get_function(trait_func_n)
+    }
+
+    /// Returns the sieve seeing only the trait `trait_id`, if the table has it.
+    #[inline(always)]
+    pub(crate) fn query_sieve(&self, trait_id: TraitId) -> Option<usize> {
+        let position = self
+            .trait_ids
+            .iter()
+            .position(|id| *id == trait_id && *id != NO_TRAIT_ID)?;
+        Some(1 << position)
+    }
+}
+
+/// The packed pointers: a pointer to a table aligned to 2^8 bytes, with the sieve in its low 8
+/// bits, for up to 8 traits.
+impl<T: TraitTable, const TRAIT_N: usize> SieveTable<T, Align256, TRAIT_N> {
+    /// A mask over the pointer, without the sieve.
+    pub(crate) const SIEVE_MASK: usize = (!0) << 8;
+
+    /// Returns the pointer to the table, with a sieve seeing all of its traits.
+    pub(crate) fn pack(&self) -> usize {
+        let sieve = usize::MAX >> (usize::BITS - TRAIT_N as u32);
+        assert!(Self::SIEVE_MASK > sieve, "More than 8 traits");
+        let ptr: *const Self = self;
+        (ptr as usize) | sieve
+    }
+
+    /// Returns the table `mangled` points to.
+    ///
+    /// # Safety
+    ///
+    /// `mangled` must come from `pack` on a table that lives for `'vt`.
+    #[inline(always)]
+    pub(crate) unsafe fn unpack<'vt>(mangled: usize) -> &'vt Self {
+        &*((mangled & Self::SIEVE_MASK) as *const Self)
+    }
+
+    /// Returns `mangled` with its sieve replaced by `sieve`.
+    #[inline(always)]
+    pub(crate) fn with_packed_sieve(mangled: usize, sieve: usize) -> usize {
+        (mangled & Self::SIEVE_MASK) | sieve
+    }
+
+    /// Returns `mangled` seeing the traits of `mask`, as `ProjectPtr::project`.
+    #[inline(always)]
+    pub(crate) fn project_packed(mangled: usize, mask: usize) -> usize {
+        Self::with_packed_sieve(mangled, deposit_bits(mask, mangled & !Self::SIEVE_MASK))
+    }
+}
+
+// `traits_ptr` only points into `traits`, which is never modified.
+unsafe impl<T: TraitTable + Sync, A, const TRAIT_N: usize> Send for SieveTable<T, A, TRAIT_N> where
+    T::Owned: Send
+{
+}
+unsafe impl<T: TraitTable + Sync, A, const TRAIT_N: usize> Sync for SieveTable<T, A, TRAIT_N> where
+    T::Owned: Sync
+{
+}
+
+impl<T: TraitTable, A, const TRAIT_N: usize> TableFootprint for SieveTable<T, A, TRAIT_N> {
+    fn footprint(&self) -> Footprint {
+        Footprint {
+            pointer_bytes: size_of::<T::Ptr>(),
+            table_bytes: size_of_val(self),
+            heap_bytes: self.traits.capacity() * size_of::<T::Owned>()
+                + self.traits.len() * T::SEPARATE_BYTES,
+            slots: self.traits.len() * T::FUNC_N,
+            filler_slots: self
+                .traits
+                .iter()
+                .map(|t| t.borrow().footprint().filler_slots)
+                .sum(),
+        }
+    }
+}
+
+#[test]
+fn sieve_table_padding_test() {
+    use crate::{
+        packed_sieve_table::{PackedSievePtr, PackedSieveTable},
+        QueryPtr,
+    };
+
+    fn returns<const N: u64>(_: *const (), _: u64) -> u64 {
+        N
+    }
+
+    // 3 functions for 3 traits of 2: the last trait gets a table of fillers.
+    let table = PackedSieveTable::<_, 3, 2>::new_with_trait_ids(
+        &[returns::<0>, returns::<1>, returns::<2>],
+        [10, 11, 12],
+    );
+    assert!(table.traits_ptr.iter().all(|ptr| !ptr.is_null()));
+    assert!(table.footprint().filler_slots == 3);
+    let ptr = PackedSievePtr::<u64, u64, 3, 2>::new(&table)
+        .query(12)
+        .unwrap();
+    let result = std::panic::catch_unwind(|| unsafe { ptr.get_function(0)(null(), 0) });
+    assert!(result.is_err(), "the filler should panic");
+}
+
+#[test]
+#[should_panic(expected = "7 functions, maximum is 6")]
+fn sieve_table_too_many_functions_test() {
+    use crate::packed_sieve_table::{PackedSievePtr, PackedSieveTable};
+
+    fn returns(_: *const (), _: u64) -> u64 {
+        0
+    }
+    let funcs: [Function<PackedSievePtr<u64, u64, 3, 2>>; 7] = [returns; 7];
+    PackedSieveTable::<PackedSievePtr<u64, u64, 3, 2>, 3, 2>::new(&funcs);
+}
@@ -0,0 +1,261 @@
+//! Like `packed_sieve_table`, with the sieve split between the table pointer and the data pointer.
+//!
+//! `PackedSievePtr` aligns its table to 256 bytes for the 8 bits of its sieve. `SplitSievePtr`
+//! holds the data pointer too, like a `Box<dyn A+B>`, and steals the low bits of both words: the
+//! table is aligned to 16 bytes for the first `TABLE_BITS` traits, and the data must be aligned
+//! enough for the others, e.g. to 8 bytes for 3 more traits.
+use std::{marker::PhantomData, mem::align_of};
+
+use crate::{
+    sieve_table::{Align16, SieveTable},
+    v_table::VTable,
+    FuncTablePtr, Function, ProjectPtr, QueryPtr, TraitId,
+};
+
+/// The number of traits the table pointer has room for.
+pub const TABLE_BITS: u32 = 4;
+
+/// A `SieveTable` of `VTable`s, aligned to 16 bytes for the first `TABLE_BITS` traits.
+///
+/// Its footprint counts the data pointer in the pointer.
+pub type SplitSieveTable<Ptr, const TRAIT_N: usize, const FUNC_N: usize> =
+    SieveTable<VTable<Ptr, FUNC_N>, Align16, TRAIT_N>;
+
+const _: () = assert!(
+    1 << TABLE_BITS == align_of::<SplitSieveTable<crate::v_table::VPtr<(), (), 1>, 1, 1>>()
+);
+
+/// The data pointer and the table pointer, each with part of the sieve in its low bits.
+///
+/// The first `TABLE_BITS` traits are in the table pointer, the others in the data pointer.
+#[repr(C)]
+pub struct SplitSievePtr<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> {
+    pub(crate) data_mangled: usize,  // *const () | sieve >> TABLE_BITS
+    pub(crate) table_mangled: usize, // *const SplitSieveTable<Self, TRAIT_N, FUNC_N> | sieve
+    phantom: PhantomData<&'vt SplitSieveTable<Self, TRAIT_N, FUNC_N>>,
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize>
+    SplitSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    const TABLE_SIEVE: usize = !(usize::MAX << TABLE_BITS);
+    /// The number of traits in the data pointer.
+    pub const DATA_BITS: u32 = (TRAIT_N as u32).saturating_sub(TABLE_BITS);
+    const DATA_SIEVE: usize = !(usize::MAX << Self::DATA_BITS);
+
+    /// Pairs `data` with the table, seeing all of its traits.
+    ///
+    /// Panics if `T` is not aligned to `1 << DATA_BITS` bytes, e.g. if the table has 7 traits and
+    /// `T` is not aligned to 8 bytes, or if `data` itself is not.
+    pub fn new<T>(
+        data: *const T,
+        sieve_table: &'vt SplitSieveTable<Self, TRAIT_N, FUNC_N>,
+    ) -> Self {
+        assert!(TRAIT_N < usize::BITS as usize, "Too many traits");
+        assert!(
+            align_of::<T>() >> Self::DATA_BITS != 0,
+            "The data is aligned to {} bytes, {} traits need {}",
+            align_of::<T>(),
+            TRAIT_N,
+            1 << Self::DATA_BITS
+        );
+        assert!(data as usize & Self::DATA_SIEVE == 0, "Unaligned data");
+        let ptr: *const _ = sieve_table;
+        Self::from_parts(data as usize, ptr as usize, !(usize::MAX << TRAIT_N))
+    }
+
+    #[inline(always)]
+    fn from_parts(data: usize, table: usize, sieve: usize) -> Self {
+        Self {
+            data_mangled: data | sieve >> TABLE_BITS,
+            table_mangled: table | sieve & Self::TABLE_SIEVE,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the data pointer, without the sieve.
+    #[inline(always)]
+    pub fn data(&self) -> *const () {
+        (self.data_mangled & !Self::DATA_SIEVE) as *const ()
+    }
+
+    /// The bits of the sieve, from both pointers.
+    #[inline(always)]
+    fn sieve(&self) -> usize {
+        (self.table_mangled & Self::TABLE_SIEVE)
+            | (self.data_mangled & Self::DATA_SIEVE) << TABLE_BITS
+    }
+
+    #[inline(always)]
+    unsafe fn table(&self) -> &'vt SplitSieveTable<Self, TRAIT_N, FUNC_N> {
+        let ptr = self.table_mangled & !Self::TABLE_SIEVE;
+        &*(ptr as *const SplitSieveTable<Self, TRAIT_N, FUNC_N>)
+    }
+
+    /// Calls the `n`-th function with the data.
+    ///
+    /// # Safety
+    ///
+    /// Behavior is undefined if the pointer knows about fewer than `n` functions.
+    #[inline(always)]
+    pub unsafe fn call(&self, n: u32, arg: ArgT) -> RetT {
+        self.get_function(n)(self.data(), arg)
+    }
+
+    /// Returns a pointer to the same data and table, with another sieve.
+    #[inline(always)]
+    fn with_sieve(&self, sieve: usize) -> Self {
+        Self::from_parts(
+            self.data_mangled & !Self::DATA_SIEVE,
+            self.table_mangled & !Self::TABLE_SIEVE,
+            sieve,
+        )
+    }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> Copy
+    for SplitSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+}
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> Clone
+    for SplitSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> FuncTablePtr
+    for SplitSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    type Arg = ArgT;
+    type Ret = RetT;
+    type Markers = ();
+    #[inline(always)]
+    unsafe fn get_function(&self, n: u32) -> Function<Self> {
+        self.table().get_function(self.sieve(), n)
+    }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> ProjectPtr
+    for SplitSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    #[inline(always)]
+    unsafe fn project(&self, mask: usize) -> Self {
+        self.with_sieve(crate::select_bit::deposit_bits(mask, self.sieve()))
+    }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> QueryPtr
+    for SplitSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    #[inline(always)]
+    fn query(&self, trait_id: TraitId) -> Option<Self> {
+        // The pointer was built from a reference to the table.
+        let sieve = unsafe { self.table() }.query_sieve(trait_id)?;
+        Some(self.with_sieve(sieve))
+    }
+}
+
+#[cfg(test)]
+fn add<const K: u64>(data: *const (), x: u64) -> u64 {
+    x + K + unsafe { *(data as *const u8) } as u64
+}
+
+/// A table of `TRAIT_N` traits of one function, the `k`-th adding `k` to its argument.
+#[cfg(test)]
+fn make_add_table<P, const TRAIT_N: usize>() -> SplitSieveTable<P, TRAIT_N, 1>
+where
+    P: FuncTablePtr<Arg = u64, Ret = u64>,
+{
+    let funcs: [Function<P>; 7] = [
+        add::<0>, add::<1>, add::<2>, add::<3>, add::<4>, add::<5>, add::<6>,
+    ];
+    SplitSieveTable::new(&funcs[..TRAIT_N])
+}
+
+// Up to 4 traits, the data pointer is not tagged.
+const _: () = assert!(SplitSievePtr::<u64, u64, 4, 1>::DATA_BITS == 0);
+const _: () = assert!(SplitSievePtr::<u64, u64, 5, 1>::DATA_BITS == 1);
+const _: () = assert!(SplitSievePtr::<u64, u64, 7, 1>::DATA_BITS == 3);
+
+#[test]
+fn split_sieve_layout_test() {
+    type Ptr = SplitSievePtr<'static, u64, u64, 7, 1>;
+    assert!(std::mem::size_of::<Ptr>() == 2 * std::mem::size_of::<usize>());
+    let table = make_add_table::<Ptr, 7>();
+    assert!(&table as *const _ as usize % 16 == 0);
+}
+
+#[test]
+fn split_sieve_project_test() {
+    #[repr(align(8))]
+    struct Aligned(u8);
+
+    let data = Aligned(100);
+    let table = make_add_table::<_, 7>();
+    let ptr = SplitSievePtr::new(&data, &table);
+    assert!(ptr.data() == &data as *const Aligned as *const ());
+    for n in 0..7 {
+        assert!(unsafe { ptr.call(n, 0) } == data.0 as u64 + n as u64);
+    }
+
+    // Traits on both sides of the boundary: the 4th in the table pointer, the 5th in the data.
+    let ptr = unsafe { ptr.project(0b001_1000) };
+    assert!(ptr.data() == &data as *const Aligned as *const ());
+    assert!(unsafe { ptr.call(0, 0) } == 103);
+    assert!(unsafe { ptr.call(1, 0) } == 104);
+    // Only in the data pointer, then only in the table pointer.
+    let last = unsafe { ptr.project(0b10) };
+    assert!(unsafe { last.call(0, 0) } == 104);
+    let first = unsafe { ptr.project(0b01) };
+    assert!(unsafe { first.call(0, 0) } == 103);
+    assert!(first.data() == last.data());
+}
+
+#[test]
+fn split_sieve_alignment_test() {
+    let bytes = [7u8, 8];
+    // Up to 4 traits, the data pointer is not tagged: any alignment goes.
+    let table = make_add_table::<_, 4>();
+    let ptr = SplitSievePtr::new(&bytes[1], &table);
+    assert!(ptr.data() == &bytes[1] as *const u8 as *const ());
+    assert!(unsafe { ptr.project(0b1000).call(0, 0) } == 11);
+
+    // 5 traits need 2-byte alignment, 6 and 7 need 4 and 8.
+    let half = 3u16;
+    let table = make_add_table::<_, 5>();
+    let ptr = SplitSievePtr::new(&half, &table);
+    assert!(unsafe { ptr.project(0b10000).call(0, 0) } == 7);
+    let word = 5u64;
+    let table = make_add_table::<_, 7>();
+    let ptr = SplitSievePtr::new(&word, &table);
+    assert!(unsafe { ptr.project(0b1000000).call(0, 0) } == 11);
+
+    // Hiding every trait leaves the data pointer intact.
+    let none = unsafe { ptr.project(0) };
+    assert!(none.data() == &word as *const u64 as *const ());
+}
+
+#[test]
+#[should_panic(expected = "The data is aligned to 1 bytes, 5 traits need 2")]
+fn split_sieve_unaligned_test() {
+    let table = make_add_table::<_, 5>();
+    SplitSievePtr::new(&0u8, &table);
+}
+
+#[test]
+#[should_panic(expected = "The data is aligned to 4 bytes, 7 traits need 8")]
+fn split_sieve_underaligned_test() {
+    let table = make_add_table::<_, 7>();
+    SplitSievePtr::new(&0u32, &table);
+}
+
+#[test]
+#[should_panic(expected = "Unaligned data")]
+fn split_sieve_unaligned_data_test() {
+    let words = [0u64; 2];
+    let table = make_add_table::<_, 7>();
+    let data = (words.as_ptr() as *const u8).wrapping_add(4) as *const u64;
+    SplitSievePtr::new(data, &table);
+}
@@ -0,0 +1,247 @@
+//! Owned objects behind any of the pointer representations: the equivalent of `Box<dyn A+B>`.
+//!
+//! The functions in the tables receive the boxed value as their data. Besides, the box measures
+//! what owning it costs: allocation, one more word for the data pointer, and the drop entry.
+//!
+//! With a `Marked` pointer, the box is `Send` or `Sync` like `Box<dyn A + Send + Sync>`. With a
+//! `Typed` pointer, it implements the traits declared with `#[table_trait]`.
+use std::{mem::ManuallyDrop, ptr::NonNull};
+
+use crate::{
+    markers::{HasMarkers, Marked, MarkerSet, SubsetOf},
+    typed::{SieveLayout, TraitSet, Typed},
+    FuncTablePtr, ProjectPtr,
+};
+
+/// Drops and frees a value allocated by `SumBox::new`.
+type DropFn = unsafe fn(NonNull<u8>);
+
+/// The drop entry for `T`.
+///
+/// # Safety
+///
+/// Behavior is undefined unless `data` comes from a `Box<T>` that was not dropped already.
+unsafe fn drop_boxed<T>(data: NonNull<u8>) {
+    drop(Box::from_raw(data.as_ptr() as *mut T));
+}
+
+/// An owned object together with its table pointer.
+///
+/// `P` is one of `VPtr`, `MultiVPtr`, `PackedSievePtr` or `InlineSievePtr`, possibly `Marked`.
+pub struct SumBox<P: FuncTablePtr> {
+    data: NonNull<u8>,
+    meta: P,
+    drop: DropFn,
+}
+
+impl<P: FuncTablePtr> SumBox<P> {
+    /// Moves `value` to the heap and pairs it with `meta`.
+    ///
+    /// # Safety
+    ///
+    /// Behavior is undefined unless the functions of `meta` expect a pointer to a `T` as their
+    /// data, such as those of the tables `typed` builds for `T`: the methods `#[table_trait]`
+    /// implements for a `SumBox` of a `Typed` pointer call them without further checks.
+    pub unsafe fn new<T: HasMarkers<P::Markers>>(value: T, meta: P) -> Self {
+        let data = NonNull::from(Box::leak(Box::new(value))).cast();
+        Self {
+            data,
+            meta,
+            drop: drop_boxed::<T>,
+        }
+    }
+
+    /// Returns the table pointer.
+    #[inline(always)]
+    pub fn meta(&self) -> P {
+        self.meta
+    }
+
+    /// Returns a pointer to the boxed value.
+    #[inline(always)]
+    pub fn as_ptr(&self) -> *const u8 {
+        self.data.as_ptr()
+    }
+
+    /// Calls the `n`-th function of the object.
+    ///
+    /// # Safety
+    ///
+    /// Behavior is undefined if the pointer knows about fewer than `n` functions. The function
+    /// expects a pointer to the boxed value as its data, as `new` requires.
+    #[inline(always)]
+    pub unsafe fn call(&self, n: u32, arg: P::Arg) -> P::Ret {
+        self.meta.get_function(n)(self.data.as_ptr() as *const (), arg)
+    }
+}
+
+impl<P: ProjectPtr> SumBox<P> {
+    /// Converts to a box that only sees a subset of the traits, without reallocating.
+    ///
+    /// See `ProjectPtr::project` for the meaning of `mask`.
+    ///
+    /// # Safety
+    ///
+    /// Behavior is undefined if `mask` has bits set beyond the number of visible traits.
+    #[inline(always)]
+    pub unsafe fn project(mut self, mask: usize) -> Self {
+        self.meta = self.meta.project(mask);
+        self
+    }
+}
+
+impl<P: SieveLayout, S: TraitSet> SumBox<Typed<P, S>> {
+    /// Like `project`, to the traits of `D`, with the checks of `Typed::project`.
+    #[inline(always)]
+    pub fn project_to<D: TraitSet>(self) -> SumBox<Typed<P, D>> {
+        let this = ManuallyDrop::new(self);
+        SumBox {
+            data: this.data,
+            meta: this.meta.project(),
+            drop: this.drop,
+        }
+    }
+}
+
+impl<P: FuncTablePtr, M: ?Sized + MarkerSet> SumBox<Marked<P, M>> {
+    /// Drops some of the markers, e.g. `Box<dyn A + Send>` → `Box<dyn A>`.
+    #[inline(always)]
+    pub fn forget<N>(self) -> SumBox<Marked<P, N>>
+    where
+        N: ?Sized + SubsetOf<M>,
+    {
+        let this = ManuallyDrop::new(self);
+        SumBox {
+            data: this.data,
+            meta: this.meta.forget(),
+            drop: this.drop,
+        }
+    }
+}
+
+/// The value is `Send`, as `SumBox::new` checked.
+unsafe impl<P> Send for SumBox<P>
+where
+    P: FuncTablePtr + Send,
+    dyn Send: SubsetOf<P::Markers>,
+{
+}
+
+/// The value is `Sync`, as `SumBox::new` checked: `call` shares it with the functions.
+unsafe impl<P> Sync for SumBox<P>
+where
+    P: FuncTablePtr + Sync,
+    dyn Sync: SubsetOf<P::Markers>,
+{
+}
+
+impl<P: FuncTablePtr> Drop for SumBox<P> {
+    fn drop(&mut self) {
+        unsafe { (self.drop)(self.data) }
+    }
+}
+
+#[cfg(test)]
+fn returns<const N: u64>(_: *const (), _: u64) -> u64 {
+    N
+}
+
+#[test]
+fn sum_box_drop_test() {
+    use std::{cell::Cell, rc::Rc};
+
+    use crate::v_table::{VPtr, VTable};
+
+    struct CountDrops(Rc<Cell<u32>>);
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    let drops = Rc::new(Cell::new(0));
+    let table = VTable::new([returns::<7>]);
+    let boxed = unsafe { SumBox::new(CountDrops(drops.clone()), VPtr::new(&table)) };
+    assert!(unsafe { boxed.call(0, 0) } == 7);
+    assert!(drops.get() == 0);
+    drop(boxed);
+    assert!(drops.get() == 1);
+
+    let zst = unsafe { SumBox::new((), VPtr::new(&table)) };
+    drop(zst);
+}
+
+#[test]
+fn sum_box_packed_sieve_project_test() {
+    use crate::packed_sieve_table::{PackedSievePtr, PackedSieveTable};
+
+    let table = PackedSieveTable::<_, 3, 2>::new(&[
+        returns::<0>,
+        returns::<1>,
+        returns::<2>,
+        returns::<3>,
+        returns::<4>,
+        returns::<5>,
+    ]);
+    let boxed = unsafe { SumBox::new(String::from("abc"), PackedSievePtr::new(&table)) };
+    let data = boxed.as_ptr();
+    let boxed = unsafe { boxed.project(0b101) };
+    assert!(boxed.as_ptr() == data);
+    assert!(unsafe { boxed.call(0, 0) } == 0);
+    assert!(unsafe { boxed.call(1, 0) } == 1);
+    assert!(unsafe { boxed.call(2, 0) } == 4);
+    assert!(unsafe { boxed.call(3, 0) } == 5);
+    let boxed = unsafe { boxed.project(0b10) };
+    assert!(unsafe { boxed.call(0, 0) } == 4);
+}
+
+#[test]
+fn sum_box_inline_sieve_project_test() {
+    use crate::inline_sieve_table::{InlineSievePtr, InlineSieveTable};
+
+    let table = InlineSieveTable::new([
+        Some(returns::<0>),
+        None,
+        Some(returns::<1>),
+        Some(returns::<2>),
+    ]);
+    let boxed = unsafe { SumBox::new(vec![1u8, 2, 3], InlineSievePtr::new(&table)) };
+    let boxed = unsafe { boxed.project(0b110) };
+    assert!(unsafe { boxed.call(0, 0) } == 1);
+    assert!(unsafe { boxed.call(1, 0) } == 2);
+}
+
+#[test]
+fn sum_box_marked_test() {
+    use std::{sync::Arc, thread};
+
+    use crate::packed_sieve_table::{PackedSievePtr, PackedSieveTable};
+
+    fn read_vec(data: *const (), n: u64) -> u64 {
+        let vec = unsafe { &*(data as *const Vec<u64>) };
+        vec[n as usize]
+    }
+
+    let table = PackedSieveTable::<_, 3, 1>::new(&[returns::<0>, returns::<1>, read_vec]);
+    let meta = Marked::<_, dyn Send + Sync>::new(PackedSievePtr::new(&table));
+    let boxed = unsafe { SumBox::new(vec![10u64, 20, 30], meta) };
+    let boxed = unsafe { boxed.project(0b101) };
+    thread::scope(|s| {
+        s.spawn(|| assert!(unsafe { boxed.call(1, 2) } == 30));
+    });
+    let boxed: SumBox<Marked<_, dyn Send>> = boxed.forget();
+    let boxed = thread::scope(|s| {
+        s.spawn(move || {
+            assert!(unsafe { boxed.call(0, 0) } == 0);
+            boxed
+        })
+        .join()
+        .unwrap()
+    });
+    let boxed: SumBox<Marked<_, ()>> = boxed.forget();
+    assert!(unsafe { boxed.call(1, 1) } == 20);
+
+    // An `Arc` is `Send` and `Sync`, a `Cell` is `Send` only.
+    let _ = unsafe { SumBox::new(Arc::new(1), meta) };
+    let _ = unsafe { SumBox::new(std::cell::Cell::new(1), meta.forget::<dyn Send>()) };
+}
@@ -0,0 +1,134 @@
+//! A table of `(trait id, table)` pairs, searched when converting to a sum of traits.
+//!
+//! Each type has a single table listing the tables of all its traits, sorted by trait id.
+//! Converting to a sum of traits searches each trait of the sum, and the pointer caches the
+//! result: the index of each trait's table, one byte per trait. Calls then only index.
+use std::{
+    marker::PhantomData,
+    mem::{size_of, size_of_val},
+};
+
+use crate::{
+    footprint::{Footprint, TableFootprint},
+    v_table::VTable,
+    FuncTable, FuncTablePtr, Function, ProjectPtr,
+};
+
+pub struct TraitSearchTable<Ptr: FuncTablePtr, const FUNC_N: usize> {
+    /// Sorted.
+    trait_ids: Vec<u32>,
+    /// The table of each trait of `trait_ids`, in the same order.
+    tables: Vec<VTable<Ptr, FUNC_N>>,
+}
+
+impl<Ptr: FuncTablePtr, const FUNC_N: usize> TraitSearchTable<Ptr, FUNC_N> {
+    pub fn new(mut traits: Vec<(u32, VTable<Ptr, FUNC_N>)>) -> Self {
+        assert!(traits.len() <= 256, "More than 256 traits");
+        traits.sort_by_key(|(id, _)| *id);
+        assert!(
+            traits.windows(2).all(|w| w[0].0 != w[1].0),
+            "duplicate trait id"
+        );
+        let (trait_ids, tables) = traits.into_iter().unzip();
+        Self { trait_ids, tables }
+    }
+
+    /// Returns the index of the table of `trait_id`, if the type implements it.
+    pub fn find(&self, trait_id: u32) -> Option<u8> {
+        self.trait_ids
+            .binary_search(&trait_id)
+            .ok()
+            .map(|index| index as u8)
+    }
+}
+
+impl<Ptr: FuncTablePtr, const FUNC_N: usize> TableFootprint for TraitSearchTable<Ptr, FUNC_N> {
+    fn footprint(&self) -> Footprint {
+        Footprint {
+            pointer_bytes: size_of::<Ptr>(),
+            table_bytes: size_of_val(self),
+            heap_bytes: self.trait_ids.capacity() * size_of::<u32>()
+                + self.tables.capacity() * size_of::<VTable<Ptr, FUNC_N>>(),
+            slots: self.tables.len() * FUNC_N,
+            filler_slots: self.tables.iter().map(|t| t.footprint().filler_slots).sum(),
+        }
+    }
+}
+
+#[repr(C)]
+pub struct TraitSearchPtr<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> {
+    pub(crate) tables: *const VTable<Self, FUNC_N>,
+    /// The index in `tables` of each trait of the sum.
+    pub(crate) indices: [u8; TRAIT_N],
+    phantom: PhantomData<&'vt TraitSearchTable<Self, FUNC_N>>,
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize>
+    TraitSearchPtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    /// Returns a pointer to the sum of `trait_ids`, or `None` if the type misses one of them.
+    pub fn new(
+        table: &'vt TraitSearchTable<Self, FUNC_N>,
+        trait_ids: [u32; TRAIT_N],
+    ) -> Option<Self> {
+        let mut indices = [0; TRAIT_N];
+        for (index, trait_id) in indices.iter_mut().zip(trait_ids) {
+            *index = table.find(trait_id)?;
+        }
+        Some(Self {
+            tables: table.tables.as_ptr(),
+            indices,
+            phantom: PhantomData,
+        })
+    }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> Copy
+    for TraitSearchPtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+}
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> Clone
+    for TraitSearchPtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> FuncTablePtr
+    for TraitSearchPtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    type Arg = ArgT;
+    type Ret = RetT;
+    type Markers = ();
+    #[inline(always)]
+    unsafe fn get_function(&self, n: u32) -> Function<Self> {
+        let trait_n = n / (FUNC_N as u32);
+        let trait_func_n = n % (FUNC_N as u32);
+        let index = *self.indices.get_unchecked(trait_n as usize);
+        (*self.tables.add(index as usize)).get_function(trait_func_n)
+    }
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> ProjectPtr
+    for TraitSearchPtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    /// Keeps the cached indices of the traits in `mask`, without searching again.
+    #[inline(always)]
+    unsafe fn project(&self, mask: usize) -> Self {
+        let mut indices = [0; TRAIT_N];
+        let kept = self
+            .indices
+            .iter()
+            .enumerate()
+            .filter(|(rank, _)| mask & (1 << rank) != 0);
+        for (index, (_, kept)) in indices.iter_mut().zip(kept) {
+            *index = *kept;
+        }
+        Self {
+            tables: self.tables,
+            indices,
+            phantom: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,449 @@
+//! Projections checked at compile time, over `PackedSievePtr` and `InlineSievePtr`.
+//!
+//! `Typed<P, S>` is a pointer that sees the traits of the tuple `S`, in order, each trait being a
+//! type implementing `TraitMarker`, such as those `#[table_trait]` generates. `project` computes
+//! the mask of `ProjectPtr::project` at compile time, and `method` the index of a method. Both
+//! fail to compile, rather than return a wrong pointer, if the traits are not among the visible
+//! ones:
+//!
+//! ```compile_fail
+//! # use bench_vtables::{packed_sieve_table::PackedSievePtr, typed::*, TraitId};
+//! # struct A;
+//! # impl TraitMarker for A { const ID: TraitId = 1; const FUNC_N: usize = 1; }
+//! # struct B;
+//! # impl TraitMarker for B { const ID: TraitId = 2; const FUNC_N: usize = 1; }
+//! fn project(ptr: Typed<PackedSievePtr<u64, u64, 2, 1>, (A,)>) {
+//!     ptr.project::<(B,)>();
+//! }
+//! # println!("{:p}", project as fn(_));
+//! ```
+//!
+//! ```compile_fail
+//! # use bench_vtables::{packed_sieve_table::PackedSievePtr, typed::*, TraitId};
+//! # struct A;
+//! # impl TraitMarker for A { const ID: TraitId = 1; const FUNC_N: usize = 1; }
+//! # struct B;
+//! # impl TraitMarker for B { const ID: TraitId = 2; const FUNC_N: usize = 1; }
+//! fn call(ptr: Typed<PackedSievePtr<u64, u64, 2, 1>, (A,)>) {
+//!     ptr.method::<B, 0>();
+//! }
+//! # println!("{:p}", call as fn(_));
+//! ```
+//!
+//! These are errors of constant evaluation, in the functions that get compiled: `cargo build`
+//! reports them, `cargo check` does not.
+use std::marker::PhantomData;
+
+use crate::{
+    filler_function,
+    inline_sieve_table::{InlineSievePtr, InlineSieveTable},
+    markers::{Marked, MarkerSet},
+    multi_ptrs::MultiVTable,
+    packed_sieve_table::{PackedSievePtr, PackedSieveTable},
+    v_table::VTable,
+    FuncTablePtr, Function, ProjectPtr, TraitId, NO_TRAIT_ID,
+};
+
+/// Stands for a trait at the type level.
+pub trait TraitMarker {
+    const ID: TraitId;
+    /// The number of methods of the trait.
+    const FUNC_N: usize;
+}
+
+/// A tuple of `TraitMarker`s.
+pub trait TraitSet {
+    /// The id and number of methods of each trait, in order.
+    const TRAITS: &'static [(TraitId, usize)];
+}
+
+macro_rules! trait_set {
+    ($($t:ident),*) => {
+        impl<$($t: TraitMarker),*> TraitSet for ($($t,)*) {
+            const TRAITS: &'static [(TraitId, usize)] = &[$(($t::ID, $t::FUNC_N)),*];
+        }
+    };
+}
+
+trait_set!();
+trait_set!(A);
+trait_set!(A, B);
+trait_set!(A, B, C);
+trait_set!(A, B, C, D);
+trait_set!(A, B, C, D, E);
+trait_set!(A, B, C, D, E, F);
+trait_set!(A, B, C, D, E, F, G);
+trait_set!(A, B, C, D, E, F, G, H);
+
+/// How the visible functions and the mask bits of a pointer map to its traits.
+pub trait SieveLayout: ProjectPtr {
+    /// `Some(n)` if each trait has one bit and `n` slots, its methods followed by fillers. `None`
+    /// if each method has its own bit.
+    const TRAIT_SLOTS: Option<usize>;
+}
+
+impl<'vt, ArgT, RetT, const TRAIT_N: usize, const FUNC_N: usize> SieveLayout
+    for PackedSievePtr<'vt, ArgT, RetT, TRAIT_N, FUNC_N>
+{
+    const TRAIT_SLOTS: Option<usize> = Some(FUNC_N);
+}
+
+impl<'vt, ArgT, RetT, const N: usize> SieveLayout for InlineSievePtr<'vt, ArgT, RetT, N> {
+    const TRAIT_SLOTS: Option<usize> = None;
+}
+
+impl<P: SieveLayout, M: ?Sized + MarkerSet> SieveLayout for Marked<P, M> {
+    const TRAIT_SLOTS: Option<usize> = P::TRAIT_SLOTS;
+}
+
+/// Returns the mask selecting the traits of `to` among those of `from`.
+///
+/// Panics unless `to` is a subsequence of `from`.
+const fn project_mask(
+    from: &[(TraitId, usize)],
+    to: &[(TraitId, usize)],
+    trait_slots: Option<usize>,
+) -> usize {
+    let mut mask = 0;
+    let mut bit = 0;
+    let mut t = 0;
+    let mut f = 0;
+    while f < from.len() {
+        let bits = match trait_slots {
+            Some(_) => 1,
+            None => from[f].1,
+        };
+        if t < to.len() && from[f].0 == to[t].0 {
+            let mut b = 0;
+            while b < bits {
+                mask |= 1 << (bit + b);
+                b += 1;
+            }
+            t += 1;
+        }
+        bit += bits;
+        f += 1;
+    }
+    assert!(
+        t == to.len(),
+        "the traits are not among the visible ones, in the same order"
+    );
+    mask
+}
+
+/// Returns the index of the `n`-th method of trait `id` among the functions of `traits`.
+///
+/// Panics if `traits` has no such trait or the trait no such method.
+const fn function_index(
+    traits: &[(TraitId, usize)],
+    id: TraitId,
+    n: usize,
+    trait_slots: Option<usize>,
+) -> u32 {
+    let mut index = 0;
+    let mut r = 0;
+    while r < traits.len() {
+        let (trait_id, func_n) = traits[r];
+        if let Some(slots) = trait_slots {
+            assert!(func_n <= slots, "the trait has more methods than slots");
+        }
+        if trait_id == id {
+            assert!(n < func_n, "the trait has no such method");
+            return (index + n) as u32;
+        }
+        index += match trait_slots {
+            Some(slots) => slots,
+            None => func_n,
+        };
+        r += 1;
+    }
+    panic!("the trait is not among the visible ones")
+}
+
+/// A method in the tables, for any pointer with these argument and return types.
+pub type MethodFn<Arg, Ret> = fn(*const (), Arg) -> Ret;
+
+/// The functions of a trait for the type `T`, as `#[table_trait]` generates them.
+pub trait TraitFunctions<T>: TraitMarker {
+    type Arg: 'static;
+    type Ret: 'static;
+    /// Each method of `T`, taking a pointer to a `T` as its data.
+    const FUNCTIONS: &'static [MethodFn<Self::Arg, Self::Ret>];
+}
+
+/// A tuple of `TraitFunctions` with the same argument and return types.
+pub trait SetFunctions<T>: TraitSet {
+    type Arg: 'static;
+    type Ret: 'static;
+    /// The functions of each trait, in order.
+    fn functions() -> Vec<&'static [MethodFn<Self::Arg, Self::Ret>]>;
+}
+
+macro_rules! set_functions {
+    ($first:ident $(, $t:ident)*) => {
+        impl<T, $first, $($t),*> SetFunctions<T> for ($first, $($t,)*)
+        where
+            $first: TraitFunctions<T>,
+            $($t: TraitFunctions<T, Arg = $first::Arg, Ret = $first::Ret>,)*
+        {
+            type Arg = $first::Arg;
+            type Ret = $first::Ret;
+            fn functions() -> Vec<&'static [MethodFn<Self::Arg, Self::Ret>]> {
+                vec![$first::FUNCTIONS, $($t::FUNCTIONS),*]
+            }
+        }
+    };
+}
+
+set_functions!(A);
+set_functions!(A, B);
+set_functions!(A, B, C);
+set_functions!(A, B, C, D);
+set_functions!(A, B, C, D, E);
+set_functions!(A, B, C, D, E, F);
+set_functions!(A, B, C, D, E, F, G);
+set_functions!(A, B, C, D, E, F, G, H);
+
+/// The functions of the traits of `S` for `T`, each trait followed by fillers up to `FUNC_N`.
+fn padded_functions<S, T, P, const FUNC_N: usize>() -> Vec<Function<P>>
+where
+    S: SetFunctions<T>,
+    P: FuncTablePtr<Arg = S::Arg, Ret = S::Ret>,
+{
+    let mut funcs = Vec::new();
+    for trait_funcs in S::functions() {
+        assert!(
+            trait_funcs.len() <= FUNC_N,
+            "trait has {} methods, maximum is {}",
+            trait_funcs.len(),
+            FUNC_N
+        );
+        funcs.extend_from_slice(trait_funcs);
+        funcs.resize(
+            funcs.len() + FUNC_N - trait_funcs.len(),
+            filler_function::<P>,
+        );
+    }
+    funcs
+}
+
+/// Returns the table of `T` for the traits of `S`, one after the other.
+pub fn v_table<S, T, P, const N: usize>() -> VTable<P, N>
+where
+    S: SetFunctions<T>,
+    P: FuncTablePtr<Arg = S::Arg, Ret = S::Ret>,
+{
+    VTable::new_from_slice(&S::functions().concat())
+}
+
+/// Returns the table of `T` for the traits of `S`, each trait followed by fillers up to `FUNC_N`.
+/// A `MultiVPtr` to it sees the traits of `S`.
+pub fn multi_v_table<S, T, P, const TRAIT_N: usize, const FUNC_N: usize>(
+) -> MultiVTable<P, TRAIT_N, FUNC_N>
+where
+    S: SetFunctions<T>,
+    P: FuncTablePtr<Arg = S::Arg, Ret = S::Ret>,
+{
+    MultiVTable::new(&padded_functions::<S, T, P, FUNC_N>())
+}
+
+/// Returns the table of `T` for the traits of `S`, named by their ids. A `PackedSievePtr` to it
+/// sees the traits of `S`.
+pub fn packed_sieve_table<S, T, P, const TRAIT_N: usize, const FUNC_N: usize>(
+) -> PackedSieveTable<P, TRAIT_N, FUNC_N>
+where
+    S: SetFunctions<T>,
+    P: FuncTablePtr<Arg = S::Arg, Ret = S::Ret>,
+{
+    assert!(
+        S::TRAITS.len() == TRAIT_N,
+        "{} traits for {} slots",
+        S::TRAITS.len(),
+        TRAIT_N
+    );
+    let mut trait_ids = [NO_TRAIT_ID; TRAIT_N];
+    for (id, (trait_id, _)) in trait_ids.iter_mut().zip(S::TRAITS) {
+        *id = *trait_id;
+    }
+    PackedSieveTable::new_with_trait_ids(&padded_functions::<S, T, P, FUNC_N>(), trait_ids)
+}
+
+/// Returns the table of `T` for the traits of `S`, named by their ids, without fillers. An
+/// `InlineSievePtr` to it sees the traits of `S`.
+pub fn inline_sieve_table<S, T, P, const N: usize>() -> InlineSieveTable<P, N>
+where
+    S: SetFunctions<T>,
+    P: FuncTablePtr<Arg = S::Arg, Ret = S::Ret>,
+{
+    let mut opt_funcs = [None; N];
+    let mut traits = Vec::new();
+    let mut slot = 0;
+    for ((trait_id, _), trait_funcs) in S::TRAITS.iter().zip(S::functions()) {
+        assert!(slot + trait_funcs.len() <= N, "more than {} methods", N);
+        let mut sieve = 0;
+        for func in trait_funcs {
+            opt_funcs[slot] = Some(*func);
+            sieve |= 1 << slot;
+            slot += 1;
+        }
+        traits.push((*trait_id, sieve));
+    }
+    InlineSieveTable::new_with_traits(opt_funcs, &traits)
+}
+
+/// A pointer that sees the traits of `S`.
+#[repr(transparent)]
+pub struct Typed<P, S> {
+    ptr: P,
+    traits: PhantomData<fn() -> S>,
+}
+
+impl<P: SieveLayout, S: TraitSet> Typed<P, S> {
+    /// # Safety
+    ///
+    /// Behavior is undefined unless `ptr` sees the traits of `S`, in order, with as many methods.
+    pub unsafe fn new(ptr: P) -> Self {
+        Self {
+            ptr,
+            traits: PhantomData,
+        }
+    }
+
+    /// Returns the pointer, without the traits.
+    #[inline(always)]
+    pub fn ptr(self) -> P {
+        self.ptr
+    }
+
+    /// Returns a pointer that only sees the traits of `D`, which must be visible in the same order.
+    #[inline(always)]
+    pub fn project<D: TraitSet>(self) -> Typed<P, D> {
+        let mask = const { project_mask(S::TRAITS, D::TRAITS, P::TRAIT_SLOTS) };
+        Typed {
+            ptr: unsafe { self.ptr.project(mask) },
+            traits: PhantomData,
+        }
+    }
+
+    /// Returns the `N`-th method of the trait `T`, which must be visible.
+    #[inline(always)]
+    pub fn method<T: TraitMarker, const N: usize>(self) -> Function<P> {
+        let index = const { function_index(S::TRAITS, T::ID, N, P::TRAIT_SLOTS) };
+        unsafe { self.ptr.get_function(index) }
+    }
+}
+
+impl<P: Copy, S> Copy for Typed<P, S> {}
+impl<P: Copy, S> Clone for Typed<P, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+/// Functions by index, to pair a `Typed` with data; there is no `ProjectPtr` with raw masks.
+impl<P: SieveLayout, S: TraitSet> FuncTablePtr for Typed<P, S> {
+    type Arg = P::Arg;
+    type Ret = P::Ret;
+    type Markers = P::Markers;
+    #[inline(always)]
+    unsafe fn get_function(&self, n: u32) -> Function<Self> {
+        self.ptr.get_function(n)
+    }
+}
+
+#[cfg(test)]
+fn returns<const N: u64>(_: *const (), _: u64) -> u64 {
+    N
+}
+
+macro_rules! trait_marker {
+    ($name:ident, $id:literal, $func_n:literal) => {
+        #[cfg(test)]
+        struct $name;
+        #[cfg(test)]
+        impl TraitMarker for $name {
+            const ID: TraitId = $id;
+            const FUNC_N: usize = $func_n;
+        }
+    };
+}
+
+trait_marker!(A, 1, 2);
+trait_marker!(B, 2, 2);
+trait_marker!(C, 3, 1);
+trait_marker!(C2, 4, 1);
+
+#[cfg(test)]
+fn call(f: fn(*const (), u64) -> u64) -> u64 {
+    f(std::ptr::null(), 0)
+}
+
+#[test]
+fn typed_packed_sieve_test() {
+    use crate::packed_sieve_table::PackedSieveTable;
+
+    type Ptr<'vt> = PackedSievePtr<'vt, u64, u64, 3, 2>;
+    // C has a single method, followed by a filler.
+    let table = PackedSieveTable::<Ptr, 3, 2>::new(&[
+        returns::<0>,
+        returns::<1>,
+        returns::<2>,
+        returns::<3>,
+        returns::<4>,
+    ]);
+    let ptr = unsafe { Typed::<_, (A, B, C)>::new(PackedSievePtr::new(&table)) };
+    assert!(call(ptr.method::<A, 1>()) == 1);
+    assert!(call(ptr.method::<C, 0>()) == 4);
+    let ptr = ptr.project::<(A, C)>();
+    assert!(call(ptr.method::<A, 0>()) == 0);
+    assert!(call(ptr.method::<C, 0>()) == 4);
+    let ptr = ptr.project::<(C,)>();
+    assert!(call(ptr.method::<C, 0>()) == 4);
+    assert!(call(unsafe { ptr.get_function(0) }) == 4);
+}
+
+#[test]
+fn typed_inline_sieve_test() {
+    use crate::inline_sieve_table::InlineSieveTable;
+
+    type Ptr<'vt> = InlineSievePtr<'vt, u64, u64, 6>;
+    // The traits have as many bits as methods, and a slot they do not see.
+    let table = InlineSieveTable::<Ptr, 6>::new([
+        Some(returns::<0>),
+        Some(returns::<1>),
+        None,
+        Some(returns::<2>),
+        None,
+        Some(returns::<4>),
+    ]);
+    let ptr = unsafe { Typed::<_, (A, C, C2)>::new(InlineSievePtr::new(&table)) };
+    assert!(call(ptr.method::<C, 0>()) == 2);
+    assert!(call(ptr.method::<C2, 0>()) == 4);
+    let ptr = ptr.project::<(A, C2)>();
+    assert!(call(ptr.method::<A, 1>()) == 1);
+    assert!(call(ptr.method::<C2, 0>()) == 4);
+    let ptr = ptr.project::<(C2,)>();
+    assert!(call(ptr.method::<C2, 0>()) == 4);
+}
+
+#[test]
+fn typed_marked_test() {
+    use crate::packed_sieve_table::PackedSieveTable;
+
+    type Ptr<'vt> = Marked<PackedSievePtr<'vt, u64, u64, 2, 2>, dyn Send>;
+    let table = PackedSieveTable::<_, 2, 2>::new(&[returns::<0>, returns::<1>, returns::<2>]);
+    let ptr: Ptr = Marked::new(PackedSievePtr::new(&table));
+    let ptr = unsafe { Typed::<_, (B, C)>::new(ptr) }.project::<(C,)>();
+    assert!(call(ptr.method::<C, 0>()) == 2);
+}
+
+#[test]
+fn project_mask_test() {
+    let abc = <(A, B, C)>::TRAITS;
+    assert!(project_mask(abc, <(A, C)>::TRAITS, Some(2)) == 0b101);
+    assert!(project_mask(abc, <(A, C)>::TRAITS, None) == 0b10011);
+    assert!(project_mask(abc, <()>::TRAITS, None) == 0);
+    assert!(function_index(abc, C::ID, 0, Some(2)) == 4);
+    assert!(function_index(abc, C::ID, 0, None) == 4);
+    assert!(function_index(<(B, C)>::TRAITS, C::ID, 0, None) == 2);
+}
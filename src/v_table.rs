@@ -1,7 +1,14 @@
-use crate::{filler_function, FuncTable, FuncTablePtr, Function};
+use std::mem::size_of;
 
+use crate::{
+    filler_function,
+    footprint::{count_fillers, Footprint, TableFootprint},
+    FuncTable, FuncTablePtr, Function,
+};
+
+#[repr(C)]
 pub struct VTable<Ptr: FuncTablePtr, const N: usize> {
-    funcs: [Function<Ptr>; N],
+    pub(crate) funcs: [Function<Ptr>; N],
 }
 
 impl<Ptr: FuncTablePtr, const N: usize> VTable<Ptr, N> {
@@ -9,7 +16,7 @@ impl<Ptr: FuncTablePtr, const N: usize> VTable<Ptr, N> {
         Self { funcs }
     }
     pub fn new_with_filler() -> Self {
-        Self::new([filler_function; N])
+        Self::new([filler_function::<Ptr>; N])
     }
     pub fn new_from_slice(funcs: &[Function<Ptr>]) -> Self {
         assert!(
@@ -18,7 +25,7 @@ impl<Ptr: FuncTablePtr, const N: usize> VTable<Ptr, N> {
             funcs.len(),
             N
         );
-        let mut array: [Function<Ptr>; N] = [filler_function; N];
+        let mut array: [Function<Ptr>; N] = [filler_function::<Ptr>; N];
         array[..funcs.len()].copy_from_slice(funcs);
         Self::new(array)
     }
@@ -39,8 +46,21 @@ impl<PtrT: FuncTablePtr, const N: usize> FuncTable for VTable<PtrT, N> {
         *func_ptr
     }
 }
+impl<Ptr: FuncTablePtr, const N: usize> TableFootprint for VTable<Ptr, N> {
+    fn footprint(&self) -> Footprint {
+        Footprint {
+            pointer_bytes: size_of::<Ptr>(),
+            table_bytes: size_of::<Self>(),
+            heap_bytes: 0,
+            slots: N,
+            filler_slots: count_fillers::<Ptr>(&self.funcs),
+        }
+    }
+}
+
+#[repr(C)]
 pub struct VPtr<'vt, ArgT, RetT, const N: usize> {
-    table: &'vt VTable<Self, N>,
+    pub(crate) table: &'vt VTable<Self, N>,
 }
 
 impl<'vt, ArgT, RetT, const N: usize> VPtr<'vt, ArgT, RetT, N> {
@@ -59,6 +79,7 @@ impl<'vt, ArgT, RetT, const N: usize> Clone for VPtr<'vt, ArgT, RetT, N> {
 impl<'vt, ArgT, RetT, const N: usize> FuncTablePtr for VPtr<'vt, ArgT, RetT, N> {
     type Arg = ArgT;
     type Ret = RetT;
+    type Markers = ();
     #[inline(always)]
     unsafe fn get_function(&self, n: u32) -> Function<Self> {
         self.table.get_function(n)
@@ -0,0 +1,107 @@
+//! A table provider, built as a shared library and loaded by `tests/table_provider.rs`.
+//!
+//! Its types implement the traits of `bench_vtables::provider` with functions the host never
+//! links:
+//!
+//! | Type | `ARITH` | `BITS` |
+//! | -:|:-:|:-:|
+//! | 0 | `x + 1`, `x * 2` | `x << 1`, `x.count_ones()` |
+//! | 1 | `x - 1`, `x / 2` | `x >> 1`, `x.leading_zeros()` |
+use std::sync::OnceLock;
+
+use bench_vtables::provider::{InlineTable, PackedTable, ARITH, BITS, FUNC_N, INLINE_N, TRAIT_N};
+
+type Method = fn(u64) -> u64;
+
+const TYPES: [[Method; TRAIT_N * FUNC_N]; 2] = [
+    [
+        |x| x.wrapping_add(1),
+        |x| x.wrapping_mul(2),
+        |x| x << 1,
+        |x| x.count_ones() as u64,
+    ],
+    [
+        |x| x.wrapping_sub(1),
+        |x| x / 2,
+        |x| x >> 1,
+        |x| x.leading_zeros() as u64,
+    ],
+];
+
+/// Adapts the `n`-th method of type `TYPE` to the function type of the tables. The types have no
+/// data, the method ignores it.
+fn method<const TYPE: usize, const N: usize>(_: *const (), x: u64) -> u64 {
+    TYPES[TYPE][N](x)
+}
+
+macro_rules! methods {
+    ($type:literal) => {
+        [
+            method::<$type, 0>,
+            method::<$type, 1>,
+            method::<$type, 2>,
+            method::<$type, 3>,
+        ]
+    };
+}
+
+type Function = fn(*const (), u64) -> u64;
+
+fn make_packed_table(funcs: [Function; TRAIT_N * FUNC_N]) -> PackedTable {
+    PackedTable::new_with_trait_ids(&funcs, [ARITH, BITS])
+}
+
+fn make_inline_table(funcs: [Function; TRAIT_N * FUNC_N]) -> InlineTable {
+    let mut opt_funcs = [None; INLINE_N];
+    let mut traits = [(ARITH, 0), (BITS, 0)];
+    for (n, func) in funcs.iter().enumerate() {
+        let slot = n / FUNC_N * (FUNC_N + 1) + n % FUNC_N;
+        opt_funcs[slot] = Some(*func);
+        traits[n / FUNC_N].1 |= 1 << slot;
+    }
+    InlineTable::new_with_traits(opt_funcs, &traits)
+}
+
+struct Tables {
+    packed: Vec<PackedTable>,
+    inline: Vec<InlineTable>,
+}
+
+// The tables are never modified once built.
+unsafe impl Send for Tables {}
+unsafe impl Sync for Tables {}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| Tables {
+        packed: vec![
+            make_packed_table(methods!(0)),
+            make_packed_table(methods!(1)),
+        ],
+        inline: vec![
+            make_inline_table(methods!(0)),
+            make_inline_table(methods!(1)),
+        ],
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn provider_type_count() -> u32 {
+    TYPES.len() as u32
+}
+
+#[no_mangle]
+pub extern "C" fn provider_packed_table(type_id: u32) -> *const PackedTable {
+    match tables().packed.get(type_id as usize) {
+        Some(table) => table,
+        None => std::ptr::null(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn provider_inline_table(type_id: u32) -> *const InlineTable {
+    match tables().inline.get(type_id as usize) {
+        Some(table) => table,
+        None => std::ptr::null(),
+    }
+}
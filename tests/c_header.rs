@@ -0,0 +1,35 @@
+//! Compiles `include/bench_vtables.h` with the C compiler, which checks its static assertions.
+//!
+//! The test is skipped when `cc` is not installed.
+use std::{env, fs, io::ErrorKind, path::Path, process::Command};
+
+#[test]
+fn c_header_test() {
+    let source = Path::new(env!("CARGO_TARGET_TMPDIR")).join("c_header_test.c");
+    fs::write(
+        &source,
+        "#include \"bench_vtables.h\"\nint main(void) { return 0; }\n",
+    )
+    .unwrap();
+    let include = Path::new(env!("CARGO_MANIFEST_DIR")).join("include");
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let output = Command::new(cc)
+        .args(["-std=c11", "-Wall", "-Werror", "-fsyntax-only"])
+        .arg("-I")
+        .arg(include)
+        .arg(&source)
+        .output();
+    let output = match output {
+        Ok(output) => output,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            eprintln!("cc not found, skipping");
+            return;
+        }
+        Err(e) => panic!("failed to run cc: {}", e),
+    };
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
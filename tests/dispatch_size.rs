@@ -0,0 +1,131 @@
+//! Counts the instructions of the dispatch sequences of `examples/dispatch_probes.rs`.
+//!
+//! Fails if a sequence grows beyond its budget. The budgets are for x86_64: the probes are built
+//! for `CPUS` rather than for the native CPU of `.cargo/config.toml`, so that the counts don't
+//! depend on the machine. The test is skipped, saying so, when `objdump` is not installed. Run
+//! with `--nocapture` to see the counts.
+#![cfg(target_arch = "x86_64")]
+
+use std::{
+    env,
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+/// The CPUs the probes are built for: with BMI2, then without.
+const CPUS: [&str; 2] = ["x86-64-v3", "x86-64"];
+
+/// Maximum number of instructions of each probe, `ret` included, for each of `CPUS`.
+///
+/// Without BMI2, `find_nth_set_bit` can't use `pdep` and unrolls a loop instead. The budgets leave
+/// about a fifth of headroom over the counts of rustc 1.95, and at least one instruction, for
+/// other compiler versions.
+const BUDGETS: [(&str, [usize; 2]); 18] = [
+    ("probe_vptr_get_function", [4, 4]),
+    ("probe_vptr_get_function_0", [3, 3]),
+    ("probe_vptr_get_function_6", [3, 3]),
+    ("probe_multi_vptr_get_function", [11, 11]),
+    ("probe_multi_vptr_get_function_0", [4, 4]),
+    ("probe_multi_vptr_get_function_6", [4, 4]),
+    ("probe_packed_sieve_get_function", [44, 176]),
+    ("probe_packed_sieve_get_function_0", [6, 6]),
+    ("probe_packed_sieve_get_function_6", [9, 11]),
+    ("probe_inline_sieve_get_function", [26, 150]),
+    ("probe_inline_sieve_get_function_0", [4, 4]),
+    ("probe_inline_sieve_get_function_6", [6, 18]),
+    ("probe_hybrid_sieve_get_function", [45, 176]),
+    ("probe_hybrid_sieve_get_function_0", [3, 3]),
+    ("probe_hybrid_sieve_get_function_6", [9, 11]),
+    ("probe_split_sieve_get_function", [34, 156]),
+    ("probe_split_sieve_get_function_0", [14, 12]),
+    ("probe_split_sieve_get_function_6", [16, 21]),
+];
+
+/// Builds the probes in release mode for `cpu` and returns the path of the executable.
+///
+/// Each CPU has its own target directory, so that the flags don't rebuild the main one.
+fn build_probes(cpu: &str) -> PathBuf {
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("dispatch_probes")
+        .join(cpu);
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let status = Command::new(cargo)
+        .args(["build", "--release", "--example", "dispatch_probes"])
+        .arg("--target-dir")
+        .arg(&target_dir)
+        // Overrides the flags of `.cargo/config.toml`.
+        .env("RUSTFLAGS", format!("-C target-cpu={}", cpu))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .status()
+        .expect("failed to run cargo");
+    assert!(status.success(), "failed to build the probes for {}", cpu);
+    target_dir.join("release/examples/dispatch_probes")
+}
+
+/// Returns whether `objdump` is installed.
+fn has_objdump() -> bool {
+    match Command::new("objdump").arg("--version").output() {
+        Ok(_) => true,
+        Err(e) if e.kind() == ErrorKind::NotFound => false,
+        Err(e) => panic!("failed to run objdump: {}", e),
+    }
+}
+
+/// Returns the number of instructions of `symbol`.
+fn count_instructions(exe: &Path, symbol: &str) -> usize {
+    let Output { status, stdout, .. } = Command::new("objdump")
+        .arg("-d")
+        .arg("--no-show-raw-insn")
+        .arg(format!("--disassemble={}", symbol))
+        .arg(exe)
+        .output()
+        .expect("failed to run objdump");
+    assert!(status.success(), "objdump failed on {}", exe.display());
+    // Instructions look like `   13c30:	mov    (%rdi),%rax`.
+    let count = String::from_utf8_lossy(&stdout)
+        .lines()
+        .filter(|line| match line.trim_start().split_once(':') {
+            Some((address, rest)) => {
+                !address.is_empty()
+                    && address.chars().all(|c| c.is_ascii_hexdigit())
+                    && rest.starts_with('\t')
+            }
+            None => false,
+        })
+        .count();
+    assert!(count > 0, "symbol {} not found", symbol);
+    count
+}
+
+#[test]
+fn dispatch_size_test() {
+    if !has_objdump() {
+        // Bypasses the capture of the test harness, so that the skip shows.
+        writeln!(
+            io::stderr(),
+            "dispatch_size_test skipped: objdump not found"
+        )
+        .unwrap();
+        return;
+    }
+    let mut over_budget = Vec::new();
+    for (cpu_n, cpu) in CPUS.iter().enumerate() {
+        let exe = build_probes(cpu);
+        for (symbol, budgets) in BUDGETS.iter() {
+            let budget = budgets[cpu_n];
+            let count = count_instructions(&exe, symbol);
+            println!(
+                "{:<10} {:<36} {:>3} instructions (budget {})",
+                cpu, symbol, count, budget
+            );
+            if count > budget {
+                over_budget.push(format!(
+                    "{} has {} instructions for {}, budget is {}",
+                    symbol, count, cpu, budget
+                ));
+            }
+        }
+    }
+    assert!(over_budget.is_empty(), "{}", over_budget.join("\n"));
+}
@@ -0,0 +1,79 @@
+//! Loads `table_provider/` as a shared library and dispatches through the tables it built.
+//!
+//! The host only shares `bench_vtables::provider` with the provider, as crates compiled separately
+//! would.
+use std::{env, path::PathBuf, process::Command};
+
+use bench_vtables::{
+    provider::{
+        InlinePtr, InlineTableFn, PackedPtr, PackedTableFn, TypeCountFn, ARITH, BITS,
+        INLINE_TABLE_SYMBOL, PACKED_TABLE_SYMBOL, TYPE_COUNT_SYMBOL,
+    },
+    FuncTablePtr, ProjectPtr, QueryPtr,
+};
+use libloading::{library_filename, Library};
+
+/// Builds the provider and returns the path of the library.
+fn build_provider() -> PathBuf {
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let status = Command::new(cargo)
+        .args(["build", "--package", "table_provider"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .status()
+        .expect("failed to run cargo");
+    assert!(status.success(), "failed to build the provider");
+    // The test runs from `<target>/<profile>/deps/`.
+    let exe = env::current_exe().unwrap();
+    let target_dir = exe.ancestors().nth(3).unwrap();
+    target_dir
+        .join("debug")
+        .join(library_filename("table_provider"))
+}
+
+/// Calls the `n`-th function the pointer sees. The types of the provider have no data.
+fn call<P: FuncTablePtr<Arg = u64, Ret = u64>>(ptr: P, n: u32, x: u64) -> u64 {
+    unsafe { ptr.get_function(n)(std::ptr::null(), x) }
+}
+
+/// The methods of each type of the provider, applied to 12.
+const EXPECTED: [[u64; 4]; 2] = [[13, 24, 24, 2], [11, 6, 6, 60]];
+
+#[test]
+fn table_provider_test() {
+    let library = unsafe { Library::new(build_provider()) }.expect("failed to load the provider");
+    let (type_count, packed_table, inline_table) = unsafe {
+        (
+            *library.get::<TypeCountFn>(TYPE_COUNT_SYMBOL).unwrap(),
+            *library.get::<PackedTableFn>(PACKED_TABLE_SYMBOL).unwrap(),
+            *library.get::<InlineTableFn>(INLINE_TABLE_SYMBOL).unwrap(),
+        )
+    };
+    assert!(type_count() as usize == EXPECTED.len());
+    assert!(packed_table(type_count()).is_null());
+    assert!(inline_table(type_count()).is_null());
+
+    for (type_id, expected) in EXPECTED.iter().enumerate() {
+        let packed = PackedPtr::new(unsafe { &*packed_table(type_id as u32) });
+        let inline = InlinePtr::new(unsafe { &*inline_table(type_id as u32) });
+        for n in 0..4 {
+            assert!(call(packed, n, 12) == expected[n as usize]);
+            assert!(call(inline, n, 12) == expected[n as usize]);
+        }
+
+        // `Box<Arith + Bits>` to `Box<Bits>`.
+        let packed_bits = unsafe { packed.project(0b10) };
+        assert!(call(packed_bits, 0, 12) == expected[2]);
+        assert!(call(packed_bits, 1, 12) == expected[3]);
+        let inline_bits = unsafe { inline.project(0b1100) };
+        assert!(call(inline_bits, 0, 12) == expected[2]);
+        assert!(call(inline_bits, 1, 12) == expected[3]);
+
+        // And back, by id.
+        let packed_arith = packed_bits.query(ARITH).unwrap();
+        assert!(call(packed_arith, 1, 12) == expected[1]);
+        let inline_arith = inline_bits.query(ARITH).unwrap();
+        assert!(call(inline_arith, 1, 12) == expected[1]);
+        assert!(packed.query(BITS + 1).is_none());
+        assert!(inline.query(BITS + 1).is_none());
+    }
+}
@@ -0,0 +1,116 @@
+//! Tables generated by `#[table_trait]`, called through `SumBox`es like `Box<dyn Arith + Bits>`.
+use bench_vtables::{
+    inline_sieve_table::InlineSievePtr,
+    multi_ptrs::MultiVPtr,
+    packed_sieve_table::PackedSievePtr,
+    sum_box::SumBox,
+    table_trait,
+    typed::{self, TraitMarker, Typed},
+    v_table::VPtr,
+    FuncTablePtr, QueryPtr,
+};
+
+#[table_trait(id = 1)]
+pub trait Arith {
+    fn add(&self, x: u64) -> u64;
+    fn mul(&self, x: u64) -> u64;
+}
+
+#[table_trait(id = 2, marker = "BitsTrait")]
+pub trait Bits {
+    fn shl(&self, x: u64) -> u64;
+    fn count_ones(&self, x: u64) -> u64;
+}
+
+/// Several arguments, passed as a tuple.
+#[table_trait(id = 3)]
+pub trait Mix {
+    fn mix(&self, x: u64, y: u32) -> u64;
+}
+
+struct Offset(u64);
+
+impl Arith for Offset {
+    fn add(&self, x: u64) -> u64 {
+        x + self.0
+    }
+    fn mul(&self, x: u64) -> u64 {
+        x * self.0
+    }
+}
+
+impl Bits for Offset {
+    fn shl(&self, x: u64) -> u64 {
+        x << self.0
+    }
+    fn count_ones(&self, x: u64) -> u64 {
+        (x.count_ones() as u64) + self.0
+    }
+}
+
+impl Mix for Offset {
+    fn mix(&self, x: u64, y: u32) -> u64 {
+        x * 10 + y as u64 + self.0
+    }
+}
+
+type Both = (ArithMarker, BitsTrait);
+
+// The slots are in the order of the methods.
+const _: () = assert!(ArithMarker::ADD == 0 && ArithMarker::MUL == 1);
+const _: () = assert!(BitsTrait::SHL == 0 && BitsTrait::COUNT_ONES == 1);
+const _: () = assert!(ArithMarker::ID == 1 && BitsTrait::ID == 2);
+const _: () = assert!(ArithMarker::FUNC_N == 2 && MixMarker::FUNC_N == 1);
+
+#[test]
+fn table_trait_packed_sieve_test() {
+    let table = typed::packed_sieve_table::<Both, Offset, _, 2, 2>();
+    let ptr = unsafe { Typed::<_, Both>::new(PackedSievePtr::new(&table)) };
+    let boxed = unsafe { SumBox::new(Offset(3), ptr) };
+    assert!(boxed.add(1) == 4);
+    assert!(boxed.mul(2) == 6);
+    assert!(boxed.shl(1) == 8);
+    assert!(boxed.count_ones(0b111) == 6);
+
+    let bits = boxed.meta().ptr().query(BitsTrait::ID).unwrap();
+    let data = boxed.as_ptr() as *const ();
+    assert!(unsafe { bits.get_function(BitsTrait::SHL as u32)(data, 2) } == 16);
+
+    let boxed = boxed.project_to::<(BitsTrait,)>();
+    assert!(boxed.shl(2) == 16);
+    assert!(boxed.count_ones(0) == 3);
+}
+
+#[test]
+fn table_trait_inline_sieve_test() {
+    let table = typed::inline_sieve_table::<Both, Offset, _, 4>();
+    let ptr = unsafe { Typed::<_, Both>::new(InlineSievePtr::new(&table)) };
+    let boxed = unsafe { SumBox::new(Offset(2), ptr) };
+    assert!(boxed.mul(5) == 10);
+    assert!(boxed.count_ones(1) == 3);
+    let boxed = boxed.project_to::<(ArithMarker,)>();
+    assert!(boxed.add(5) == 7);
+}
+
+#[test]
+fn table_trait_v_tables_test() {
+    let offset = Offset(4);
+    let data = &offset as *const Offset as *const ();
+
+    let table = typed::v_table::<(ArithMarker,), Offset, _, 2>();
+    let ptr = VPtr::new(&table);
+    assert!(unsafe { ptr.get_function(ArithMarker::MUL as u32)(data, 3) } == 12);
+
+    let table = typed::multi_v_table::<Both, Offset, _, 2, 2>();
+    let ptr = MultiVPtr::new(&table);
+    let n = (2 + BitsTrait::SHL) as u32;
+    assert!(unsafe { ptr.get_function(n)(data, 1) } == 16);
+}
+
+#[test]
+fn table_trait_arguments_test() {
+    let table = typed::packed_sieve_table::<(MixMarker,), Offset, _, 1, 1>();
+    let ptr = unsafe { Typed::<_, (MixMarker,)>::new(PackedSievePtr::new(&table)) };
+    let boxed = unsafe { SumBox::new(Offset(1), ptr) };
+    assert!(boxed.mix(4, 2) == 43);
+}